@@ -0,0 +1,86 @@
+use cosmwasm_std::{
+    entry_point, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
+use cw2::set_contract_version;
+use rhaki_cw_plus::traits::{IntoAddr, IntoBinaryResult};
+use variable_manager_pkg::factory::{Config, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+
+use crate::{
+    execute::{
+        assert_owner, run_create_manager, run_manager_instantiated, run_set_manager_code_id,
+        run_update_owners, INSTANTIATE_REPLY_ID,
+    },
+    migration::{assert_and_set_version, CONTRACT_NAME, CONTRACT_VERSION},
+    query::{qy_manager, qy_managers},
+    response::{ContractError, ContractResponse},
+    state::CONFIG,
+};
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResponse {
+    if msg.owners.is_empty() {
+        return Err(ContractError::NoOwners {});
+    }
+
+    let config = Config {
+        owners: msg
+            .owners
+            .iter()
+            .map(|owner| -> StdResult<Addr> { owner.into_addr(deps.api) })
+            .collect::<StdResult<Vec<Addr>>>()?,
+        manager_code_id: msg.manager_code_id,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("owners", format!("{:?}", msg.owners))
+        .add_attribute("manager_code_id", msg.manager_code_id.to_string()))
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResponse {
+    assert_owner(deps.as_ref(), &info)?;
+
+    match msg {
+        ExecuteMsg::CreateManager(msg) => run_create_manager(deps, msg),
+        ExecuteMsg::SetManagerCodeId { code_id } => run_set_manager_code_id(deps, code_id),
+        ExecuteMsg::UpdateOwners { add, remove } => run_update_owners(deps, add, remove),
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => CONFIG.load(deps.storage).into_binary(),
+        QueryMsg::Manager { protocol } => qy_manager(deps, protocol).into_binary(),
+        QueryMsg::Managers { start_after, limit } => {
+            qy_managers(deps, start_after, limit).into_binary()
+        }
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResponse {
+    let from = assert_and_set_version(deps)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> ContractResponse {
+    match msg.id {
+        INSTANTIATE_REPLY_ID => run_manager_instantiated(deps, env, msg),
+        id => Err(ContractError::InvalidReplyId { id }),
+    }
+}
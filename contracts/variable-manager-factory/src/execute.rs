@@ -0,0 +1,159 @@
+use cosmwasm_std::{
+    attr, Addr, Attribute, Deps, DepsMut, Env, MessageInfo, Reply, Response, SubMsg, WasmMsg,
+};
+use cw_utils::parse_reply_instantiate_data;
+use rhaki_cw_plus::traits::{IntoAddr, IntoBinary};
+use variable_manager_pkg::factory::{CreateManagerMsg, ManagerInfo};
+
+use crate::{
+    response::{ContractError, ContractResponse, ContractResult},
+    state::{CONFIG, MANAGERS, PENDING_PROTOCOL},
+};
+
+/// Reply id of the instantiation of a manager
+pub const INSTANTIATE_REPLY_ID: u64 = 1;
+
+const MAX_PROTOCOL_LEN: usize = 64;
+
+pub fn run_create_manager(deps: DepsMut, msg: CreateManagerMsg) -> ContractResponse {
+    assert_protocol(&msg.protocol)?;
+
+    if MANAGERS.has(deps.storage, msg.protocol.clone()) {
+        return Err(ContractError::ProtocolAlreadyRegistered {
+            protocol: msg.protocol,
+        });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+
+    PENDING_PROTOCOL.save(deps.storage, &msg.protocol)?;
+
+    let instantiate = WasmMsg::Instantiate {
+        admin: msg
+            .admin
+            .map(|admin| -> ContractResult<String> { Ok(admin.into_addr(deps.api)?.to_string()) })
+            .transpose()?,
+        code_id: config.manager_code_id,
+        msg: msg.msg.into_binary()?,
+        funds: vec![],
+        label: format!("variable-manager-{}", msg.protocol),
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(instantiate, INSTANTIATE_REPLY_ID))
+        .add_attribute("action", "create_manager")
+        .add_attribute("protocol", msg.protocol)
+        .add_attribute("code_id", config.manager_code_id.to_string()))
+}
+
+pub fn run_set_manager_code_id(deps: DepsMut, code_id: u64) -> ContractResponse {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    config.manager_code_id = code_id;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_manager_code_id")
+        .add_attribute("code_id", code_id.to_string()))
+}
+
+pub fn run_update_owners(
+    deps: DepsMut,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+) -> ContractResponse {
+    if add.is_none() && remove.is_none() {
+        return Err(ContractError::InvalidUpdateOwnersMsg {});
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut attrs: Vec<Attribute> = vec![];
+
+    for address in add.unwrap_or_default() {
+        let address = address.into_addr(deps.api)?;
+
+        if config.owners.contains(&address) {
+            return Err(ContractError::IsAlreadyOwner { addr: address });
+        }
+
+        attrs.push(attr("owner_added", address.clone()));
+        config.owners.push(address);
+    }
+
+    let remove = remove
+        .unwrap_or_default()
+        .into_iter()
+        .map(|address| -> ContractResult<Addr> {
+            let address = address.into_addr(deps.api)?;
+
+            if !config.owners.contains(&address) {
+                return Err(ContractError::IsNotOwner { addr: address });
+            }
+
+            attrs.push(attr("owner_removed", address.clone()));
+
+            Ok(address)
+        })
+        .collect::<ContractResult<Vec<Addr>>>()?;
+
+    config.owners.retain(|owner| !remove.contains(owner));
+
+    if config.owners.is_empty() {
+        return Err(ContractError::NoOwners {});
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_owners")
+        .add_attributes(attrs))
+}
+
+/// Record the manager instantiated by `run_create_manager`
+pub fn run_manager_instantiated(deps: DepsMut, env: Env, msg: Reply) -> ContractResponse {
+    let protocol = PENDING_PROTOCOL.load(deps.storage)?;
+    PENDING_PROTOCOL.remove(deps.storage);
+
+    let address = parse_reply_instantiate_data(msg)?
+        .contract_address
+        .into_addr(deps.api)?;
+
+    let manager = ManagerInfo {
+        protocol: protocol.clone(),
+        address: address.clone(),
+        code_id: CONFIG.load(deps.storage)?.manager_code_id,
+        created_at: env.block.height,
+    };
+
+    MANAGERS.save(deps.storage, protocol.clone(), &manager)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "manager_created")
+        .add_attribute("protocol", protocol)
+        .add_attribute("manager", address))
+}
+
+pub fn assert_owner(deps: Deps, info: &MessageInfo) -> ContractResult<()> {
+    if !CONFIG.load(deps.storage)?.owners.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+fn assert_protocol(protocol: &str) -> ContractResult<()> {
+    let valid = !protocol.is_empty()
+        && protocol.len() <= MAX_PROTOCOL_LEN
+        && protocol
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+
+    if !valid {
+        return Err(ContractError::InvalidProtocol {
+            protocol: protocol.to_string(),
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+#[cfg(not(feature = "library"))]
+pub mod contract;
+mod execute;
+mod migration;
+mod query;
+mod response;
+mod state;
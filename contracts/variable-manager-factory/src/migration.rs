@@ -0,0 +1,30 @@
+use cosmwasm_std::DepsMut;
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+
+use crate::response::{ContractError, ContractResult};
+
+pub const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Check the stored cw2 version and set the current one. Return the version migrated from
+pub fn assert_and_set_version(deps: DepsMut) -> ContractResult<String> {
+    let stored = get_contract_version(deps.storage)?;
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {
+            contract: stored.contract,
+        });
+    }
+
+    if stored.version.parse::<Version>()? > CONTRACT_VERSION.parse::<Version>()? {
+        return Err(ContractError::InvalidMigrationVersion {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(stored.version)
+}
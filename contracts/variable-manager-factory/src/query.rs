@@ -0,0 +1,29 @@
+use cosmwasm_std::{Deps, Order, StdResult};
+use cw_storage_plus::Bound;
+use variable_manager_pkg::factory::ManagerInfo;
+
+use crate::state::MANAGERS;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn qy_manager(deps: Deps, protocol: String) -> StdResult<ManagerInfo> {
+    MANAGERS.load(deps.storage, protocol)
+}
+
+pub fn qy_managers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ManagerInfo>> {
+    MANAGERS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, manager)| manager))
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
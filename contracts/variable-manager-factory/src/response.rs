@@ -0,0 +1,48 @@
+use cosmwasm_std::{Addr, Response, StdError};
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+
+pub type ContractResponse = Result<Response, ContractError>;
+pub type ContractResult<T> = Result<T, ContractError>;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("{0}")]
+    Semver(#[from] semver::Error),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid 0 owners. Needed at least 1")]
+    NoOwners {},
+
+    #[error("Protocol {protocol} already registered")]
+    ProtocolAlreadyRegistered { protocol: String },
+
+    #[error("Invalid protocol name \"{protocol}\": only lowercase alphanumeric chars, '-' and '_' are allowed, up to 64 chars")]
+    InvalidProtocol { protocol: String },
+
+    #[error("Invalid reply id: {id}")]
+    InvalidReplyId { id: u64 },
+
+    #[error("Empty update owners msg")]
+    InvalidUpdateOwnersMsg {},
+
+    #[error("Address is already an owner: {addr}")]
+    IsAlreadyOwner { addr: Addr },
+
+    #[error("Address is not an owner: {addr}")]
+    IsNotOwner { addr: Addr },
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidContractName { contract: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    InvalidMigrationVersion { from: String, to: String },
+}
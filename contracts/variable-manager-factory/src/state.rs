@@ -0,0 +1,10 @@
+use cw_storage_plus::{Item, Map};
+use variable_manager_pkg::factory::{Config, ManagerInfo};
+
+pub const CONFIG: Item<Config> = Item::new("config_key");
+
+/// Managers by protocol name
+pub const MANAGERS: Map<String, ManagerInfo> = Map::new("managers_key");
+
+/// Protocol of the manager being instantiated, consumed by the instantiate reply
+pub const PENDING_PROTOCOL: Item<String> = Item::new("pending_protocol_key");
@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use variable_manager_pkg::msgs::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use variable_manager_pkg::msgs::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
-        query: QueryMsg
+        query: QueryMsg,
+        migrate: MigrateMsg
     }
 }
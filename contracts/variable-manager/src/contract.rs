@@ -1,20 +1,40 @@
 use cosmwasm_std::{
-    entry_point, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+    SubMsgResult,
 };
+use cw2::set_contract_version;
 use rhaki_cw_plus::traits::{IntoAddr, IntoBinaryResult};
 use variable_manager_pkg::{
-    definitions::Config,
+    definitions::{Config, DEFAULT_MAX_REFERENCE_DEPTH},
     msgs::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
 };
 
 use crate::{
     execute::{
-        run_register_variable, run_register_variables, run_remove_variable, run_update_owner_msg,
-        run_update_variable, run_update_variables,
+        run_accept_ownership, run_batch, run_cancel_pending, run_cancel_scheduled,
+        run_close_proposal, run_compare_and_swap, run_create_namespace, run_create_proposal,
+        run_delete_namespace, run_execute_pending, run_freeze_variable, run_grant_role,
+        run_nominate_owner, run_propose_update, run_prune_expired, run_read_variables,
+        run_register_variable, run_register_variables, run_remove_by_prefix, run_remove_variable,
+        run_remove_variables, run_revoke_nomination, run_revoke_role, run_schedule_variable,
+        run_set_guardian, run_set_max_reference_depth, run_set_metadata, run_set_paused,
+        run_set_schema, run_set_timelock, run_set_visibility, run_subscribe, run_unfreeze_variable,
+        run_unsubscribe, run_update_namespace_owners, run_update_owner_msg, run_update_variable,
+        run_update_variables, run_upsert_variable, run_vote, take_approved_proposal, HOOK_REPLY_ID,
     },
-    query::{qy_get_all_variables, qy_get_variable, qy_get_variables},
-    response::ContractResponse,
-    state::CONFIG,
+    migration::{assert_and_set_version, migrate_legacy_storage, CONTRACT_NAME, CONTRACT_VERSION},
+    permission::{assert_not_paused, authorize, Authorization},
+    query::{
+        qy_all_pending_updates, qy_all_roles, qy_all_schemas, qy_expiring_variables,
+        qy_frozen_variables, qy_get_all_variables, qy_get_all_variables_with_meta, qy_get_variable,
+        qy_get_variable_raw, qy_get_variable_with_meta, qy_get_variables, qy_namespace,
+        qy_namespaces, qy_owner_nominations, qy_pending_timelock, qy_pending_update, qy_proposal,
+        qy_proposals, qy_roles, qy_schedule, qy_schema, qy_subscriptions, qy_timelock,
+        qy_variable_at_height, qy_variable_history, qy_variables_by_prefix, qy_variables_by_tag,
+        qy_variables_in_range,
+    },
+    response::{ContractError, ContractResponse},
+    state::{variable_key, CONFIG},
 };
 
 #[entry_point]
@@ -24,46 +44,284 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> ContractResponse {
-    CONFIG.save(
-        deps.storage,
-        &Config {
-            owners: msg
-                .owners
-                .iter()
-                .map(|owner| -> StdResult<Addr> { owner.into_addr(deps.api) })
-                .collect::<StdResult<Vec<Addr>>>()?,
-        },
-    )?;
+    let config = Config {
+        owners: msg
+            .owners
+            .iter()
+            .map(|owner| -> StdResult<Addr> { owner.into_addr(deps.api) })
+            .collect::<StdResult<Vec<Addr>>>()?,
+        threshold: msg.threshold.unwrap_or(1),
+        guardian: msg
+            .guardian
+            .map(|guardian| guardian.into_addr(deps.api))
+            .transpose()?,
+        paused: false,
+        max_reference_depth: msg
+            .max_reference_depth
+            .unwrap_or(DEFAULT_MAX_REFERENCE_DEPTH),
+    };
+
+    config.validate()?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new().add_attribute("owners", format!("{:?}", msg.owners)))
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResponse {
-    CONFIG.load(deps.storage)?.validate_owner(&info.sender)?;
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResponse {
+    assert_not_paused(deps.as_ref(), &msg)?;
+
+    match authorize(deps.as_ref(), &info.sender, &msg)? {
+        Authorization::Owner if CONFIG.load(deps.storage)?.threshold > 1 => {
+            run_create_proposal(deps, &env, &info, msg)
+        }
+        _ => dispatch(deps, env, info, msg),
+    }
+}
+
+fn dispatch(mut deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResponse {
     match msg {
-        ExecuteMsg::RegisterVariable(msg) => run_register_variable(deps, msg),
-        ExecuteMsg::RegisterVariables(msgs) => run_register_variables(deps, msgs),
-        ExecuteMsg::RemoveVariable(msg) => run_remove_variable(deps, msg),
+        ExecuteMsg::RegisterVariable(msg) => run_register_variable(deps, &env, &info, msg),
+        ExecuteMsg::RegisterVariables(msgs) => run_register_variables(deps, &env, &info, msgs),
+        ExecuteMsg::RemoveVariable(msg) => run_remove_variable(deps, &env, &info, msg),
+        ExecuteMsg::RemoveVariables(msgs) => run_remove_variables(deps, &env, &info, msgs),
+        ExecuteMsg::RemoveByPrefix(msg) => run_remove_by_prefix(deps, &env, &info, msg),
         ExecuteMsg::UpdateOwners(msg) => run_update_owner_msg(deps, msg),
-        ExecuteMsg::UpdateVariable(msg) => run_update_variable(deps, msg),
-        ExecuteMsg::UpdateVariables(msgs) => run_update_variables(deps, msgs),
+        ExecuteMsg::UpdateVariable(msg) => run_update_variable(deps, &env, &info, msg),
+        ExecuteMsg::UpdateVariables(msgs) => run_update_variables(deps, &env, &info, msgs),
+        ExecuteMsg::GrantRole(msg) => run_grant_role(deps, msg),
+        ExecuteMsg::RevokeRole(msg) => run_revoke_role(deps, msg),
+        ExecuteMsg::SetTimelock(msg) => run_set_timelock(deps, &env, msg),
+        ExecuteMsg::ProposeUpdate(msg) => run_propose_update(deps, &env, &info, msg),
+        ExecuteMsg::ExecutePending { namespace, key } => {
+            run_execute_pending(deps, &env, variable_key(namespace, &key))
+        }
+        ExecuteMsg::CancelPending { namespace, key } => {
+            run_cancel_pending(deps, variable_key(namespace, &key))
+        }
+        ExecuteMsg::Vote { proposal_id } => run_vote(deps, &env, &info, proposal_id),
+        ExecuteMsg::ExecuteProposal { proposal_id } => {
+            let proposal = take_approved_proposal(deps.branch(), &env, proposal_id)?;
+
+            assert_not_paused(deps.as_ref(), &proposal.msg)?;
+
+            let info = MessageInfo {
+                sender: proposal.proposer,
+                funds: vec![],
+            };
+
+            Ok(dispatch(deps, env, info, proposal.msg)?
+                .add_attribute("proposal_id", proposal_id.to_string()))
+        }
+        ExecuteMsg::CloseProposal { proposal_id } => run_close_proposal(deps, &env, proposal_id),
+        ExecuteMsg::SetSchema(msg) => run_set_schema(deps, msg),
+        ExecuteMsg::SetVisibility(msg) => run_set_visibility(deps, msg),
+        ExecuteMsg::ReadVariables { namespace, keys } => {
+            run_read_variables(deps.as_ref(), &env, namespace, keys)
+        }
+        ExecuteMsg::CreateNamespace(msg) => run_create_namespace(deps, msg),
+        ExecuteMsg::DeleteNamespace { namespace } => run_delete_namespace(deps, namespace),
+        ExecuteMsg::UpdateNamespaceOwners(msg) => run_update_namespace_owners(deps, msg),
+        ExecuteMsg::Subscribe(msg) => run_subscribe(deps, msg),
+        ExecuteMsg::Unsubscribe(msg) => run_unsubscribe(deps, msg),
+        ExecuteMsg::Batch(operations) => run_batch(deps, &env, &info, operations),
+        ExecuteMsg::Upsert(msg) => run_upsert_variable(deps, &env, &info, msg),
+        ExecuteMsg::CompareAndSwap(msg) => run_compare_and_swap(deps, &env, &info, msg),
+        ExecuteMsg::PruneExpired { limit } => run_prune_expired(deps, &env, &info, limit),
+        ExecuteMsg::ScheduleVariable(msg) => run_schedule_variable(deps, &env, msg),
+        ExecuteMsg::SetMetadata(msg) => run_set_metadata(deps, &env, &info, msg),
+        ExecuteMsg::CancelScheduled {
+            namespace,
+            key,
+            activates_at,
+        } => run_cancel_scheduled(deps, &env, variable_key(namespace, &key), activates_at),
+        ExecuteMsg::FreezeVariable {
+            namespace,
+            key,
+            until,
+        } => run_freeze_variable(deps, &env, variable_key(namespace, &key), until),
+        ExecuteMsg::UnfreezeVariable { namespace, key } => {
+            run_unfreeze_variable(deps, variable_key(namespace, &key))
+        }
+        ExecuteMsg::Pause {} => run_set_paused(deps, true),
+        ExecuteMsg::Unpause {} => run_set_paused(deps, false),
+        ExecuteMsg::SetGuardian { guardian } => run_set_guardian(deps, guardian),
+        ExecuteMsg::SetMaxReferenceDepth {
+            max_reference_depth,
+        } => run_set_max_reference_depth(deps, max_reference_depth),
+        ExecuteMsg::NominateOwner {
+            address,
+            expires_at,
+        } => run_nominate_owner(deps, &env, address, expires_at),
+        ExecuteMsg::RevokeNomination { address } => run_revoke_nomination(deps, address),
+        ExecuteMsg::AcceptOwnership {} => run_accept_ownership(deps, &env, &info),
     }
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetVariable { key } => qy_get_variable(deps, key).into_binary(),
-        QueryMsg::GetVariables { keys } => qy_get_variables(deps, keys).into_binary(),
-        QueryMsg::AllVariables { start_after, limit } => {
-            qy_get_all_variables(deps, start_after, limit).into_binary()
+        QueryMsg::GetVariable { namespace, key } => {
+            qy_get_variable(deps, &env, namespace, key).into_binary()
+        }
+        QueryMsg::GetVariableRaw { namespace, key } => {
+            qy_get_variable_raw(deps, &env, namespace, key).into_binary()
         }
+        QueryMsg::GetVariables {
+            namespace,
+            keys,
+            allow_missing,
+        } => qy_get_variables(
+            deps,
+            &env,
+            namespace,
+            keys,
+            allow_missing.unwrap_or_default(),
+        )
+        .into_binary(),
+        QueryMsg::AllVariables {
+            namespace,
+            start_after,
+            limit,
+        } => qy_get_all_variables(deps, &env, namespace, start_after, limit).into_binary(),
+        QueryMsg::VariablesByPrefix {
+            namespace,
+            prefix,
+            start_after,
+            limit,
+            order,
+        } => qy_variables_by_prefix(deps, &env, namespace, prefix, start_after, limit, order)
+            .into_binary(),
+        QueryMsg::VariablesInRange {
+            namespace,
+            min,
+            max,
+            start_after,
+            limit,
+            order,
+        } => qy_variables_in_range(deps, &env, namespace, min, max, start_after, limit, order)
+            .into_binary(),
         QueryMsg::Config {} => CONFIG.load(deps.storage).into_binary(),
+        QueryMsg::VariableHistory {
+            namespace,
+            key,
+            start_after,
+            limit,
+        } => qy_variable_history(deps, namespace, key, start_after, limit).into_binary(),
+        QueryMsg::VariableAtHeight {
+            namespace,
+            key,
+            height,
+        } => qy_variable_at_height(deps, namespace, key, height).into_binary(),
+        QueryMsg::Roles { address } => qy_roles(deps, address).into_binary(),
+        QueryMsg::AllRoles { start_after, limit } => {
+            qy_all_roles(deps, start_after, limit).into_binary()
+        }
+        QueryMsg::OwnerNominations { start_after, limit } => {
+            qy_owner_nominations(deps, &env, start_after, limit).into_binary()
+        }
+        QueryMsg::Timelock { namespace, key } => qy_timelock(deps, namespace, key).into_binary(),
+        QueryMsg::PendingTimelock { namespace, key } => {
+            qy_pending_timelock(deps, namespace, key).into_binary()
+        }
+        QueryMsg::PendingUpdate { namespace, key } => {
+            qy_pending_update(deps, namespace, key).into_binary()
+        }
+        QueryMsg::AllPendingUpdates {
+            namespace,
+            start_after,
+            limit,
+        } => qy_all_pending_updates(deps, namespace, start_after, limit).into_binary(),
+        QueryMsg::Proposal { proposal_id } => qy_proposal(deps, proposal_id).into_binary(),
+        QueryMsg::Proposals { start_after, limit } => {
+            qy_proposals(deps, start_after, limit).into_binary()
+        }
+        QueryMsg::Schema { namespace, key } => qy_schema(deps, namespace, key).into_binary(),
+        QueryMsg::AllSchemas {
+            namespace,
+            start_after,
+            limit,
+        } => qy_all_schemas(deps, namespace, start_after, limit).into_binary(),
+        QueryMsg::Namespace { namespace } => qy_namespace(deps, namespace).into_binary(),
+        QueryMsg::Namespaces { start_after, limit } => {
+            qy_namespaces(deps, start_after, limit).into_binary()
+        }
+        QueryMsg::Subscriptions {
+            namespace,
+            start_after,
+            limit,
+        } => qy_subscriptions(deps, namespace, start_after, limit).into_binary(),
+        QueryMsg::ExpiringVariables {
+            within,
+            start_after,
+            limit,
+        } => qy_expiring_variables(deps, &env, within, start_after, limit).into_binary(),
+        QueryMsg::GetVariableWithMeta { namespace, key } => {
+            qy_get_variable_with_meta(deps, &env, namespace, key).into_binary()
+        }
+        QueryMsg::AllVariablesWithMeta {
+            namespace,
+            start_after,
+            limit,
+        } => {
+            qy_get_all_variables_with_meta(deps, &env, namespace, start_after, limit).into_binary()
+        }
+        QueryMsg::VariablesByTag {
+            namespace,
+            tag,
+            start_after,
+            limit,
+        } => qy_variables_by_tag(deps, &env, namespace, tag, start_after, limit).into_binary(),
+        QueryMsg::Schedule { namespace, key } => {
+            qy_schedule(deps, &env, namespace, key).into_binary()
+        }
+        QueryMsg::FrozenVariables {
+            namespace,
+            start_after,
+            limit,
+        } => qy_frozen_variables(deps, &env, namespace, start_after, limit).into_binary(),
     }
 }
 
 #[entry_point]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResponse {
-    Ok(Response::new())
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> ContractResponse {
+    match (msg.id, msg.result) {
+        (HOOK_REPLY_ID, SubMsgResult::Err(err)) => Ok(Response::new()
+            .add_attribute("action", "hook_failed")
+            .add_attribute("error", err)),
+        (id, _) => Err(ContractError::InvalidReplyId { id }),
+    }
+}
+
+#[entry_point]
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> ContractResponse {
+    let migrate_storage = msg.migrate_storage.unwrap_or_default();
+
+    let mut response = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    if let Some(from) = assert_and_set_version(deps.branch(), migrate_storage)? {
+        response = response.add_attribute("from_version", from);
+    }
+
+    if migrate_storage {
+        let migrated = migrate_legacy_storage(deps.storage, &env)?;
+        response = response.add_attribute("migrated_variables", migrated.to_string());
+    }
+
+    if let Some(update_owners) = msg.update_owners {
+        let update = run_update_owner_msg(deps, update_owners)?;
+        response = response.add_events(update.events).add_attributes(
+            update
+                .attributes
+                .into_iter()
+                .filter(|attribute| attribute.key != "action"),
+        );
+    }
+
+    Ok(response)
 }
@@ -1,158 +1,1867 @@
-use cosmwasm_std::{attr, Addr, Attribute, Deps, DepsMut, Response};
-use rhaki_cw_plus::traits::IntoAddr;
+use std::{cmp::Ordering, collections::BTreeMap, iter::once};
+
+use cosmwasm_schema::serde::{de::DeserializeOwned, Serialize};
+use cosmwasm_std::{
+    attr, to_json_string, Addr, Attribute, Deps, DepsMut, Env, Event, MessageInfo, Order, Response,
+    StdResult, Storage, SubMsg, Timestamp, Uint64, WasmMsg,
+};
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{Expiration, Scheduled};
+use rhaki_cw_plus::traits::{IntoAddr, IntoBinary};
 use variable_manager_pkg::{
-    definitions::Config,
-    msgs::{RegisterVariableMsg, RemoveVariableMsg, UpdateOwnerMsg},
+    definitions::{
+        Config, HookFailurePolicy, Modification, Namespace, PendingTimelock, PendingUpdate,
+        Proposal, RoleGrant, ScheduledValue, Variable, VariableAction, VariableHistoryEntry,
+        VariableMetadata, DEFAULT_NAMESPACE, MAX_TIMELOCK_DELAY,
+    },
+    msgs::{
+        CompareAndSwapMsg, CreateNamespaceMsg, ExecuteMsg, HookMsg, Operation, RegisterVariableMsg,
+        RemoveByPrefixMsg, RemoveVariableMsg, RoleMsg, ScheduleVariableMsg, SetMetadataMsg,
+        SetSchemaMsg, SetTimelockMsg, SetVisibilityMsg, SubscribeMsg, UnsubscribeMsg,
+        UpdateNamespaceOwnersMsg, UpdateOwnerMsg, VariableChangedHook,
+    },
 };
 
 use crate::{
+    query::resolve_reference,
     response::{ContractError, ContractResponse, ContractResult},
-    state::{CONFIG, VARIABLES},
+    state::{
+        active_value, expiration_index, may_load_variable, variable_key, VariableKey, CONFIG,
+        EXPIRATIONS, EXPIRING_AT_HEIGHT, EXPIRING_AT_TIME, FROZEN, HISTORY, HISTORY_COUNTER,
+        METADATA, NAMESPACES, NOMINATIONS, PENDING_TIMELOCKS, PENDING_UPDATES, PRIVATE_KEYS,
+        PROPOSALS, PROPOSAL_COUNTER, ROLES, SCHEDULES, SCHEMAS, SUBSCRIPTIONS, TAGS, TIMELOCKS,
+        VARIABLES,
+    },
 };
 
-pub fn run_register_variable(deps: DepsMut, msg: RegisterVariableMsg) -> ContractResponse {
-    if let Ok(variable) = VARIABLES.load(deps.storage, msg.key.clone()) {
+/// Seconds after which a proposal can no longer be voted or executed
+const PROPOSAL_DURATION: u64 = 7 * 24 * 60 * 60;
+
+/// Keys removed by a single `RemoveByPrefix` if `limit` is not specified
+const DEFAULT_REMOVE_LIMIT: u32 = 10;
+const MAX_REMOVE_LIMIT: u32 = 30;
+
+/// Attribute value replacing the values of private keys in the responses
+const PRIVATE_VALUE: &str = "<private>";
+
+/// Reply id of the hooks sent to subscribers with `HookFailurePolicy::Ignore`
+pub const HOOK_REPLY_ID: u64 = 1;
+
+pub fn run_register_variable(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: RegisterVariableMsg,
+) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    assert_namespace(deps.as_ref(), &key.0)?;
+
+    if let Some(variable) = may_load_variable(deps.storage, &env.block, key.clone())? {
         return Err(ContractError::KeyAlredyRegistered {
             key: msg.key,
             value: variable,
         });
     }
 
+    assert_not_frozen(deps.as_ref(), env, &key)?;
+
     let validate = msg.value.clone().validate(deps.as_ref())?;
 
-    VARIABLES.save(deps.storage, msg.key.clone(), &validate)?;
+    assert_schema(deps.as_ref(), &key, &validate)?;
+
+    assert_expiration(env, &msg.key, msg.expires_at)?;
+
+    let (hooks, event) = store_variable(
+        deps.storage,
+        env,
+        &info.sender,
+        &key,
+        Some(validate),
+        VariableAction::Register,
+    )?;
+
+    set_expiration(deps.storage, &key, msg.expires_at.unwrap_or_default())?;
+
+    let value_attr = display_value(deps.storage, &key, &msg.value);
 
     Ok(Response::new()
+        .add_submessages(hooks)
+        .add_event(event)
         .add_attribute("action", "register_variable")
+        .add_attribute("namespace", key.0)
         .add_attribute("key", msg.key)
-        .add_attribute("value", format!("{}", msg.value)))
+        .add_attribute("value", value_attr)
+        .add_attributes(
+            msg.expires_at
+                .map(|expires_at| attr("expires_at", expires_at.to_string())),
+        ))
 }
 
 pub fn run_register_variables(
     mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
     msgs: Vec<RegisterVariableMsg>,
 ) -> ContractResponse {
     let mut attrs = vec![attr("action", "register_variable")];
+    let mut hooks = vec![];
+    let mut events = vec![];
     for msg in msgs {
-        run_register_variable(deps.branch(), msg.clone())?;
-        attrs.push(attr("key", msg.key));
-        attrs.push(attr("value", format!("{}", msg.value)));
+        let response = run_register_variable(deps.branch(), env, info, msg)?;
+        hooks.extend(response.messages);
+        events.extend(response.events);
+        attrs.extend(
+            response.attributes.into_iter().filter(|attribute| {
+                matches!(attribute.key.as_str(), "namespace" | "key" | "value")
+            }),
+        );
     }
 
-    Ok(Response::new().add_attributes(attrs))
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attributes(attrs))
 }
 
-pub fn run_update_variable(deps: DepsMut, msg: RegisterVariableMsg) -> ContractResponse {
+pub fn run_update_variable(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: RegisterVariableMsg,
+) -> ContractResponse {
     let validated = msg.value.clone().validate(deps.as_ref())?;
 
-    VARIABLES.update(deps.storage, msg.key.clone(), |val| -> ContractResult<_> {
-        val.ok_or(ContractError::KeyNotFound {
-            key: msg.key.clone(),
-        })?;
-        Ok(validated)
-    })?;
+    let key = variable_key(msg.namespace, &msg.key);
+
+    if may_load_variable(deps.storage, &env.block, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: msg.key });
+    }
+
+    assert_not_timelocked(deps.as_ref(), &key)?;
+
+    assert_not_frozen(deps.as_ref(), env, &key)?;
+
+    assert_schema(deps.as_ref(), &key, &validated)?;
+
+    assert_expiration(env, &msg.key, msg.expires_at)?;
+
+    let (hooks, event) = store_variable(
+        deps.storage,
+        env,
+        &info.sender,
+        &key,
+        Some(validated),
+        VariableAction::Update,
+    )?;
+
+    if let Some(expires_at) = msg.expires_at {
+        set_expiration(deps.storage, &key, expires_at)?;
+    }
+
+    let value_attr = display_value(deps.storage, &key, &msg.value);
 
     Ok(Response::new()
+        .add_submessages(hooks)
+        .add_event(event)
         .add_attribute("action", "update_variable")
+        .add_attribute("namespace", key.0)
         .add_attribute("key", msg.key)
-        .add_attribute("value", format!("{}", msg.value)))
+        .add_attribute("value", value_attr)
+        .add_attributes(
+            msg.expires_at
+                .map(|expires_at| attr("expires_at", expires_at.to_string())),
+        ))
 }
 
-pub fn run_update_variables(mut deps: DepsMut, msgs: Vec<RegisterVariableMsg>) -> ContractResponse {
+pub fn run_update_variables(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msgs: Vec<RegisterVariableMsg>,
+) -> ContractResponse {
     let mut attrs = vec![attr("action", "update_variable")];
+    let mut hooks = vec![];
+    let mut events = vec![];
     for msg in msgs {
-        run_update_variable(deps.branch(), msg.clone())?;
-        attrs.push(attr("key", msg.key));
-        attrs.push(attr("value", format!("{}", msg.value)));
+        let response = run_update_variable(deps.branch(), env, info, msg)?;
+        hooks.extend(response.messages);
+        events.extend(response.events);
+        attrs.extend(
+            response.attributes.into_iter().filter(|attribute| {
+                matches!(attribute.key.as_str(), "namespace" | "key" | "value")
+            }),
+        );
+    }
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attributes(attrs))
+}
+
+pub fn run_upsert_variable(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: RegisterVariableMsg,
+) -> ContractResponse {
+    let key = variable_key(msg.namespace.clone(), &msg.key);
+
+    if may_load_variable(deps.storage, &env.block, key)?.is_some() {
+        run_update_variable(deps, env, info, msg)
+    } else {
+        run_register_variable(deps, env, info, msg)
+    }
+}
+
+pub fn run_compare_and_swap(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: CompareAndSwapMsg,
+) -> ContractResponse {
+    let current = may_load_variable(
+        deps.storage,
+        &env.block,
+        variable_key(msg.namespace.clone(), &msg.key),
+    )?;
+
+    if current != msg.expected {
+        let display = |variable: Option<Variable>| {
+            variable.map_or("none".to_string(), |variable| variable.to_string())
+        };
+
+        return Err(ContractError::CompareAndSwapMismatch {
+            key: msg.key,
+            expected: display(msg.expected),
+            current: display(current),
+        });
+    }
+
+    let response = run_upsert_variable(
+        deps,
+        env,
+        info,
+        RegisterVariableMsg {
+            namespace: msg.namespace,
+            key: msg.key,
+            value: msg.new,
+            expires_at: None,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessages(response.messages)
+        .add_events(response.events)
+        .add_attribute("action", "compare_and_swap")
+        .add_attributes(
+            response
+                .attributes
+                .into_iter()
+                .filter(|attribute| attribute.key != "action"),
+        ))
+}
+
+pub fn run_batch(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    operations: Vec<Operation>,
+) -> ContractResponse {
+    let mut attrs = vec![attr("action", "batch")];
+    let mut hooks = vec![];
+    let mut events = vec![];
+    for operation in operations {
+        let response = match operation {
+            Operation::Register(msg) => run_register_variable(deps.branch(), env, info, msg),
+            Operation::Update(msg) => run_update_variable(deps.branch(), env, info, msg),
+            Operation::Upsert(msg) => run_upsert_variable(deps.branch(), env, info, msg),
+            Operation::Remove(msg) => run_remove_variable(deps.branch(), env, info, msg),
+        }?;
+
+        hooks.extend(response.messages);
+        events.extend(response.events);
+        attrs.extend(response.attributes.into_iter().map(|attribute| {
+            if attribute.key == "action" {
+                attr("operation", attribute.value)
+            } else {
+                attribute
+            }
+        }));
     }
 
-    Ok(Response::new().add_attributes(attrs))
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attributes(attrs))
 }
 
-pub fn run_remove_variable(deps: DepsMut, msg: RemoveVariableMsg) -> ContractResponse {
+pub fn run_remove_variable(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: RemoveVariableMsg,
+) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
     let variable =
         VARIABLES
-            .load(deps.storage, msg.key.clone())
+            .load(deps.storage, key.clone())
             .map_err(|_| ContractError::KeyNotFound {
                 key: msg.key.to_string(),
             })?;
 
-    VARIABLES.remove(deps.storage, msg.key.clone());
+    assert_not_timelocked(deps.as_ref(), &key)?;
+
+    assert_not_frozen(deps.as_ref(), env, &key)?;
+
+    let (hooks, event) = store_variable(
+        deps.storage,
+        env,
+        &info.sender,
+        &key,
+        None,
+        VariableAction::Remove,
+    )?;
+
+    let value_attr = display_value(deps.storage, &key, &variable);
 
     Ok(Response::new()
+        .add_submessages(hooks)
+        .add_event(event)
         .add_attribute("action", "remove_variable")
+        .add_attribute("namespace", key.0)
         .add_attribute("key", msg.key)
-        .add_attribute("value", format!("{}", variable)))
+        .add_attribute("value", value_attr))
 }
 
-pub fn run_update_owner_msg(deps: DepsMut, msg: UpdateOwnerMsg) -> ContractResponse {
-    let mut config = CONFIG.load(deps.storage)?;
+pub fn run_remove_variables(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msgs: Vec<RemoveVariableMsg>,
+) -> ContractResponse {
+    let mut attrs = vec![attr("action", "remove_variable")];
+    let mut hooks = vec![];
+    let mut events = vec![];
+    for msg in msgs {
+        let response = run_remove_variable(deps.branch(), env, info, msg)?;
+        hooks.extend(response.messages);
+        events.extend(response.events);
+        attrs.extend(
+            response
+                .attributes
+                .into_iter()
+                .filter(|attribute| attribute.key != "action"),
+        );
+    }
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attributes(attrs))
+}
+
+pub fn run_remove_by_prefix(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: RemoveByPrefixMsg,
+) -> ContractResponse {
+    let namespace = msg.namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+    let limit = msg
+        .limit
+        .unwrap_or(DEFAULT_REMOVE_LIMIT)
+        .min(MAX_REMOVE_LIMIT) as usize;
+
+    let start = match msg.start_after {
+        Some(start_after) if start_after >= msg.prefix => Bound::exclusive(start_after),
+        _ => Bound::inclusive(msg.prefix.clone()),
+    };
+
+    let mut scanned = VARIABLES
+        .prefix(namespace.clone())
+        .keys(deps.storage, Some(start), None, Order::Ascending)
+        .take_while(|key| {
+            key.as_ref()
+                .map_or(true, |key| key.starts_with(&msg.prefix))
+        })
+        .take(limit + 1)
+        .collect::<StdResult<Vec<String>>>()?;
+
+    let has_more = scanned.len() > limit;
+    scanned.truncate(limit);
+
+    let mut attrs = vec![
+        attr("action", "remove_by_prefix"),
+        attr("namespace", &namespace),
+        attr("prefix", format!("{}*", msg.prefix)),
+    ];
+
+    if has_more {
+        attrs.extend(scanned.last().map(|key| attr("last_key", key)));
+    }
+
+    // Skipped keys count toward `limit`, so a call scans at most `limit` keys
+    let mut keys = vec![];
+    for key in scanned {
+        if is_locked(deps.as_ref(), env, &(namespace.clone(), key.clone()))? {
+            attrs.push(attr("skipped", key));
+        } else {
+            keys.push(key);
+        }
+    }
+
+    let mut hooks = vec![];
+    let mut events = vec![];
+    for key in keys {
+        let response = run_remove_variable(
+            deps.branch(),
+            env,
+            info,
+            RemoveVariableMsg {
+                namespace: Some(namespace.clone()),
+                key,
+            },
+        )?;
+        hooks.extend(response.messages);
+        events.extend(response.events);
+        attrs.extend(
+            response
+                .attributes
+                .into_iter()
+                .filter(|attribute| attribute.key == "key" || attribute.key == "value"),
+        );
+    }
+    attrs.push(attr("has_more", has_more.to_string()));
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attributes(attrs))
+}
+
+pub fn run_prune_expired(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    limit: Option<u32>,
+) -> ContractResponse {
+    let limit = limit.unwrap_or(DEFAULT_REMOVE_LIMIT).min(MAX_REMOVE_LIMIT) as usize;
+
+    let mut expired = vec![];
+    for (index, now) in [
+        (EXPIRING_AT_HEIGHT, env.block.height),
+        (EXPIRING_AT_TIME, env.block.time.nanos()),
+    ] {
+        expired.extend(
+            index
+                .keys(
+                    deps.storage,
+                    None,
+                    Some(Bound::exclusive((
+                        now.saturating_add(1),
+                        String::new(),
+                        String::new(),
+                    ))),
+                    Order::Ascending,
+                )
+                .take(limit - expired.len())
+                .collect::<StdResult<Vec<_>>>()?,
+        );
+    }
+
+    let mut attrs = vec![attr("action", "prune_expired")];
+    let mut hooks = vec![];
+    let mut events = vec![];
+    for (_, namespace, key) in expired {
+        let key = (namespace, key);
+        let (key_hooks, event) = store_variable(
+            deps.storage,
+            env,
+            &info.sender,
+            &key,
+            None,
+            VariableAction::Remove,
+        )?;
+        hooks.extend(key_hooks);
+        events.push(event);
+        attrs.push(attr("namespace", key.0));
+        attrs.push(attr("key", key.1));
+    }
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_events(events)
+        .add_attributes(attrs))
+}
+
+pub fn run_schedule_variable(
+    deps: DepsMut,
+    env: &Env,
+    msg: ScheduleVariableMsg,
+) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    if may_load_variable(deps.storage, &env.block, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: msg.key });
+    }
+
+    assert_not_timelocked(deps.as_ref(), &key)?;
+
+    assert_not_frozen(deps.as_ref(), env, &key)?;
+
+    let value = msg.value.validate(deps.as_ref())?;
+
+    assert_schema(deps.as_ref(), &key, &value)?;
+
+    if msg.activates_at.is_triggered(&env.block) {
+        return Err(ContractError::InvalidSchedule {
+            key: msg.key,
+            reason: format!("{} already reached", msg.activates_at),
+        });
+    }
+
+    let mut schedule = SCHEDULES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+
+    let mut position = 0;
+    for scheduled in &schedule {
+        match scheduled.activates_at.partial_cmp(&msg.activates_at) {
+            Some(Ordering::Less) => position += 1,
+            Some(Ordering::Greater) => {}
+            Some(Ordering::Equal) => {
+                return Err(ContractError::InvalidSchedule {
+                    key: msg.key,
+                    reason: format!("a value is already {}", msg.activates_at),
+                })
+            }
+            None => {
+                return Err(ContractError::InvalidSchedule {
+                    key: msg.key,
+                    reason: "heights and times can not be mixed".to_string(),
+                })
+            }
+        }
+    }
+
+    schedule.insert(
+        position,
+        ScheduledValue {
+            value: value.clone(),
+            activates_at: msg.activates_at,
+        },
+    );
+
+    SCHEDULES.save(deps.storage, key.clone(), &schedule)?;
+
+    let value_attr = display_value(deps.storage, &key, &value);
+
+    Ok(Response::new()
+        .add_attribute("action", "schedule_variable")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", msg.key)
+        .add_attribute("value", value_attr)
+        .add_attribute("activates_at", msg.activates_at.to_string()))
+}
 
-    let attrs = match (msg.add, msg.remove) {
-        (None, None) => Err(ContractError::InvalidUpdateOwnerMsg),
-        (None, Some(to_remove)) => remove_owners(deps.as_ref(), &mut config, to_remove),
-        (Some(to_add), None) => add_owners(deps.as_ref(), &mut config, to_add),
-        (Some(to_add), Some(to_remove)) => {
-            let mut combined = vec![];
-            combined.extend_from_slice(&add_owners(deps.as_ref(), &mut config, to_add)?);
-            combined.extend_from_slice(&remove_owners(deps.as_ref(), &mut config, to_remove)?);
-            Ok(combined)
+pub fn run_cancel_scheduled(
+    deps: DepsMut,
+    env: &Env,
+    key: VariableKey,
+    activates_at: Scheduled,
+) -> ContractResponse {
+    let mut schedule = SCHEDULES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+
+    let position = schedule
+        .iter()
+        .position(|scheduled| {
+            scheduled.activates_at == activates_at && !activates_at.is_triggered(&env.block)
+        })
+        .ok_or(ContractError::ScheduledValueNotFound {
+            key: key.1.clone(),
+            activates_at,
+        })?;
+
+    let cancelled = schedule.remove(position);
+
+    SCHEDULES.save(deps.storage, key.clone(), &schedule)?;
+
+    let value_attr = display_value(deps.storage, &key, &cancelled.value);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_scheduled")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", key.1)
+        .add_attribute("value", value_attr)
+        .add_attribute("activates_at", activates_at.to_string()))
+}
+
+pub fn run_set_metadata(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: SetMetadataMsg,
+) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    if may_load_variable(deps.storage, &env.block, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: msg.key });
+    }
+
+    let mut metadata = match METADATA.may_load(deps.storage, key.clone())? {
+        Some(metadata) => metadata,
+        None => {
+            let modification = Modification {
+                height: env.block.height,
+                time: env.block.time,
+                sender: info.sender.clone(),
+            };
+            VariableMetadata {
+                description: None,
+                tags: vec![],
+                created: modification.clone(),
+                updated: modification,
+            }
         }
-    }?;
+    };
+
+    let mut attrs = vec![];
+
+    if let Some(description) = msg.description {
+        attrs.push(attr("description", &description));
+        metadata.description = Some(description);
+    }
+
+    if let Some(mut tags) = msg.tags {
+        if tags.iter().any(|tag| tag.is_empty()) {
+            return Err(ContractError::InvalidMetadata {
+                key: msg.key,
+                reason: "empty tag".to_string(),
+            });
+        }
+
+        tags.sort();
+        tags.dedup();
+
+        index_tags(deps.storage, &key, &metadata.tags, &tags)?;
+
+        attrs.push(attr("tags", tags.join(",")));
+        metadata.tags = tags;
+    }
+
+    METADATA.save(deps.storage, key.clone(), &metadata)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_metadata")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", msg.key)
+        .add_attributes(attrs))
+}
+
+/// Freeze `key` until `until`, forever if `None`. A key frozen forever can not be unfrozen or frozen again.
+///
+/// Keys with an expiration can not be frozen, since expiring would remove them while frozen
+pub fn run_freeze_variable(
+    deps: DepsMut,
+    env: &Env,
+    key: VariableKey,
+    until: Option<Expiration>,
+) -> ContractResponse {
+    if may_load_variable(deps.storage, &env.block, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: key.1 });
+    }
+
+    assert_not_frozen_forever(deps.as_ref(), &key)?;
+
+    if let Some(expires_at) = EXPIRATIONS.may_load(deps.storage, key.clone())? {
+        return Err(ContractError::KeyExpiring {
+            key: key.1,
+            expires_at,
+        });
+    }
+
+    // A scheduled value would change the key while frozen
+    if SCHEDULES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default()
+        .iter()
+        .any(|scheduled| !scheduled.activates_at.is_triggered(&env.block))
+    {
+        return Err(ContractError::InvalidSchedule {
+            key: key.1,
+            reason: "cancel the scheduled values before freezing the key".to_string(),
+        });
+    }
+
+    let until = until.unwrap_or_default();
+
+    if until.is_expired(&env.block) {
+        return Err(ContractError::ExpirationReached {
+            key: key.1,
+            expires_at: until,
+        });
+    }
+
+    FROZEN.save(deps.storage, key.clone(), &until)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "freeze_variable")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", key.1)
+        .add_attribute("until", until.to_string()))
+}
+
+pub fn run_unfreeze_variable(deps: DepsMut, key: VariableKey) -> ContractResponse {
+    assert_not_frozen_forever(deps.as_ref(), &key)?;
+
+    if !FROZEN.has(deps.storage, key.clone()) {
+        return Err(ContractError::KeyNotFrozen { key: key.1 });
+    }
+
+    FROZEN.remove(deps.storage, key.clone());
+
+    Ok(Response::new()
+        .add_attribute("action", "unfreeze_variable")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", key.1))
+}
+
+pub fn run_update_owner_msg(mut deps: DepsMut, msg: UpdateOwnerMsg) -> ContractResponse {
+    let old = CONFIG.load(deps.storage)?;
+    let mut config = old.clone();
+
+    if msg.add.is_none() && msg.remove.is_none() && msg.threshold.is_none() {
+        return Err(ContractError::InvalidUpdateOwnerMsg);
+    }
+
+    let mut attrs = vec![];
+
+    if let Some(to_add) = msg.add {
+        attrs.extend(nominate_owners(
+            deps.branch(),
+            &config,
+            to_add,
+            Expiration::Never {},
+        )?);
+    }
+
+    if let Some(to_remove) = msg.remove {
+        attrs.extend(remove_owners(deps.as_ref(), &mut config, to_remove)?);
+    }
+
+    if let Some(threshold) = msg.threshold {
+        config.threshold = threshold;
+        attrs.push(attr("threshold", threshold.to_string()));
+    }
 
     config.validate()?;
 
     CONFIG.save(deps.storage, &config)?;
+
+    // Nominations don't change the owners until accepted
+    let events = if config.owners != old.owners || config.threshold != old.threshold {
+        vec![owners_event(&old, &config)?]
+    } else {
+        vec![]
+    };
+
     Ok(Response::new()
+        .add_events(events)
         .add_attribute("action", "update_owners")
         .add_attributes(attrs))
 }
 
-fn add_owners(
-    deps: Deps,
-    config: &mut Config,
-    addresses: Vec<String>,
-) -> ContractResult<Vec<Attribute>> {
-    let mut attrs: Vec<Attribute> = vec![];
+pub fn run_nominate_owner(
+    deps: DepsMut,
+    env: &Env,
+    address: String,
+    expires_at: Option<Expiration>,
+) -> ContractResponse {
+    let config = CONFIG.load(deps.storage)?;
 
-    for address in addresses {
-        let address = address.into_addr(deps.api)?;
+    let expires_at = expires_at.unwrap_or_default();
 
-        if config.owners.contains(&address) {
-            return Err(ContractError::IsAlredyOwner { addr: address });
-        }
+    if expires_at.is_expired(&env.block) {
+        return Err(ContractError::NominationExpired {
+            addr: address.into_addr(deps.api)?,
+            expires_at,
+        });
+    }
 
-        attrs.push(attr("owner_added", address.clone()));
+    let attrs = nominate_owners(deps, &config, vec![address], expires_at)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "nominate_owner")
+        .add_attributes(attrs)
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+pub fn run_revoke_nomination(deps: DepsMut, address: String) -> ContractResponse {
+    let address = address.into_addr(deps.api)?;
 
-        config.owners.push(address)
+    if !NOMINATIONS.has(deps.storage, &address) {
+        return Err(ContractError::NominationNotFound { addr: address });
     }
 
-    Ok(attrs)
+    NOMINATIONS.remove(deps.storage, &address);
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_nomination")
+        .add_attribute("address", address))
 }
 
-fn remove_owners(
-    deps: Deps,
-    config: &mut Config,
-    addresses: Vec<String>,
-) -> ContractResult<Vec<Attribute>> {
-    let mut attrs: Vec<Attribute> = vec![];
+pub fn run_accept_ownership(deps: DepsMut, env: &Env, info: &MessageInfo) -> ContractResponse {
+    let expires_at = NOMINATIONS.may_load(deps.storage, &info.sender)?.ok_or(
+        ContractError::NominationNotFound {
+            addr: info.sender.clone(),
+        },
+    )?;
 
-    let addresses = addresses
-        .into_iter()
-        .map(|address| -> ContractResult<Addr> {
-            let address = address.into_addr(deps.api)?;
+    if expires_at.is_expired(&env.block) {
+        return Err(ContractError::NominationExpired {
+            addr: info.sender.clone(),
+            expires_at,
+        });
+    }
 
-            if !config.owners.contains(&address) {
-                return Err(ContractError::IsNotOwner { addr: address });
-            }
+    let old = CONFIG.load(deps.storage)?;
+    let mut config = old.clone();
 
-            attrs.push(attr("owner_removed", address.clone()));
+    if config.owners.contains(&info.sender) {
+        return Err(ContractError::IsAlredyOwner {
+            addr: info.sender.clone(),
+        });
+    }
 
-            Ok(address)
-        })
-        .collect::<ContractResult<Vec<Addr>>>()?;
+    config.owners.push(info.sender.clone());
 
-    config.owners.retain_mut(|addr| !addresses.contains(addr));
+    config.validate()?;
 
-    Ok(attrs)
+    NOMINATIONS.remove(deps.storage, &info.sender);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(owners_event(&old, &config)?)
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner_added", info.sender.clone()))
+}
+
+pub fn run_set_paused(deps: DepsMut, paused: bool) -> ContractResponse {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    match (config.paused, paused) {
+        (true, true) => return Err(ContractError::Paused {}),
+        (false, false) => return Err(ContractError::NotPaused {}),
+        _ => config.paused = paused,
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", if paused { "pause" } else { "unpause" }))
+}
+
+pub fn run_set_max_reference_depth(deps: DepsMut, max_reference_depth: u32) -> ContractResponse {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    config.max_reference_depth = max_reference_depth;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_max_reference_depth")
+        .add_attribute("max_reference_depth", max_reference_depth.to_string()))
+}
+
+pub fn run_set_guardian(deps: DepsMut, guardian: Option<String>) -> ContractResponse {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    config.guardian = guardian
+        .map(|guardian| guardian.into_addr(deps.api))
+        .transpose()?;
+
+    config.validate()?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_guardian")
+        .add_attribute(
+            "guardian",
+            config
+                .guardian
+                .map(|guardian| guardian.to_string())
+                .unwrap_or("none".to_string()),
+        ))
+}
+
+pub fn run_grant_role(deps: DepsMut, msg: RoleMsg) -> ContractResponse {
+    let address = msg.address.into_addr(deps.api)?;
+    let grant = RoleGrant {
+        role: msg.role,
+        namespace: msg.namespace.unwrap_or(DEFAULT_NAMESPACE.to_string()),
+        prefix: msg.prefix,
+    };
+
+    assert_namespace(deps.as_ref(), &grant.namespace)?;
+
+    let mut grants = ROLES.may_load(deps.storage, &address)?.unwrap_or_default();
+
+    if grants.contains(&grant) {
+        return Err(ContractError::RoleAlredyGranted {
+            addr: address,
+            role: grant.role,
+            prefix: grant.prefix,
+        });
+    }
+
+    grants.push(grant.clone());
+
+    ROLES.save(deps.storage, &address, &grants)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("address", address)
+        .add_attribute("role", format!("{:?}", grant.role))
+        .add_attribute("namespace", grant.namespace)
+        .add_attribute("prefix", format!("{}*", grant.prefix)))
+}
+
+pub fn run_revoke_role(deps: DepsMut, msg: RoleMsg) -> ContractResponse {
+    let address = msg.address.into_addr(deps.api)?;
+    let grant = RoleGrant {
+        role: msg.role,
+        namespace: msg.namespace.unwrap_or(DEFAULT_NAMESPACE.to_string()),
+        prefix: msg.prefix,
+    };
+
+    let mut grants = ROLES.may_load(deps.storage, &address)?.unwrap_or_default();
+
+    if !grants.contains(&grant) {
+        return Err(ContractError::RoleNotGranted {
+            addr: address,
+            role: grant.role,
+            prefix: grant.prefix,
+        });
+    }
+
+    grants.retain(|val| val != &grant);
+
+    if grants.is_empty() {
+        ROLES.remove(deps.storage, &address);
+    } else {
+        ROLES.save(deps.storage, &address, &grants)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("address", address)
+        .add_attribute("role", format!("{:?}", grant.role))
+        .add_attribute("namespace", grant.namespace)
+        .add_attribute("prefix", format!("{}*", grant.prefix)))
+}
+
+/// Raise the timelock of `key` immediately. Lowering or removing it must first wait the current delay
+pub fn run_set_timelock(deps: DepsMut, env: &Env, msg: SetTimelockMsg) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    if VARIABLES.may_load(deps.storage, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: msg.key });
+    }
+
+    if let Some(delay) = msg.delay.filter(|delay| *delay > MAX_TIMELOCK_DELAY) {
+        return Err(ContractError::TimelockTooLong {
+            key: msg.key,
+            delay,
+            max: MAX_TIMELOCK_DELAY,
+        });
+    }
+
+    let current = TIMELOCKS
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+
+    let delay = msg
+        .delay
+        .map(|delay| delay.to_string())
+        .unwrap_or("none".to_string());
+
+    if msg.delay.unwrap_or_default() < current {
+        match PENDING_TIMELOCKS.may_load(deps.storage, key.clone())? {
+            Some(pending) if pending.delay == msg.delay => {
+                if env.block.time < pending.eta {
+                    return Err(ContractError::TimelockChangeNotReady {
+                        key: msg.key,
+                        eta: pending.eta,
+                    });
+                }
+            }
+            _ => {
+                let pending = PendingTimelock {
+                    delay: msg.delay,
+                    eta: time_after(env, current)?,
+                };
+
+                PENDING_TIMELOCKS.save(deps.storage, key.clone(), &pending)?;
+
+                return Ok(Response::new()
+                    .add_attribute("action", "propose_timelock")
+                    .add_attribute("namespace", key.0)
+                    .add_attribute("key", msg.key)
+                    .add_attribute("delay", delay)
+                    .add_attribute("eta", pending.eta.to_string()));
+            }
+        }
+    }
+
+    PENDING_TIMELOCKS.remove(deps.storage, key.clone());
+
+    match msg.delay {
+        Some(delay) => TIMELOCKS.save(deps.storage, key.clone(), &delay)?,
+        None => TIMELOCKS.remove(deps.storage, key.clone()),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_timelock")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", msg.key)
+        .add_attribute("delay", delay))
+}
+
+pub fn run_propose_update(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: RegisterVariableMsg,
+) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    if VARIABLES.may_load(deps.storage, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: msg.key });
+    }
+
+    if PENDING_UPDATES.has(deps.storage, key.clone()) {
+        return Err(ContractError::PendingUpdateAlredyExists { key: msg.key });
+    }
+
+    assert_not_frozen(deps.as_ref(), env, &key)?;
+
+    let delay = TIMELOCKS
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+
+    let pending = PendingUpdate {
+        value: msg.value.validate(deps.as_ref())?,
+        eta: time_after(env, delay)?,
+        proposer: info.sender.clone(),
+    };
+
+    assert_schema(deps.as_ref(), &key, &pending.value)?;
+
+    PENDING_UPDATES.save(deps.storage, key.clone(), &pending)?;
+
+    let value_attr = display_value(deps.storage, &key, &pending.value);
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_update")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", msg.key)
+        .add_attribute("value", value_attr)
+        .add_attribute("eta", pending.eta.to_string()))
+}
+
+pub fn run_execute_pending(deps: DepsMut, env: &Env, key: VariableKey) -> ContractResponse {
+    let pending = PENDING_UPDATES
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::PendingUpdateNotFound { key: key.1.clone() })?;
+
+    if env.block.time < pending.eta {
+        return Err(ContractError::PendingUpdateNotReady {
+            key: key.1,
+            eta: pending.eta,
+        });
+    }
+
+    if VARIABLES.may_load(deps.storage, key.clone())?.is_none() {
+        return Err(ContractError::KeyNotFound { key: key.1 });
+    }
+
+    assert_not_frozen(deps.as_ref(), env, &key)?;
+
+    assert_schema(deps.as_ref(), &key, &pending.value)?;
+
+    PENDING_UPDATES.remove(deps.storage, key.clone());
+
+    let (hooks, event) = store_variable(
+        deps.storage,
+        env,
+        &pending.proposer,
+        &key,
+        Some(pending.value.clone()),
+        VariableAction::Update,
+    )?;
+
+    let value_attr = display_value(deps.storage, &key, &pending.value);
+
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_event(event)
+        .add_attribute("action", "execute_pending")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", key.1)
+        .add_attribute("value", value_attr))
+}
+
+pub fn run_cancel_pending(deps: DepsMut, key: VariableKey) -> ContractResponse {
+    let pending = PENDING_UPDATES
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::PendingUpdateNotFound { key: key.1.clone() })?;
+
+    PENDING_UPDATES.remove(deps.storage, key.clone());
+
+    let value_attr = display_value(deps.storage, &key, &pending.value);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_pending")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", key.1)
+        .add_attribute("value", value_attr))
+}
+
+pub fn run_create_proposal(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    msg: ExecuteMsg,
+) -> ContractResponse {
+    let id = PROPOSAL_COUNTER.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_COUNTER.save(deps.storage, &id)?;
+
+    let proposal = Proposal {
+        msg,
+        proposer: info.sender.clone(),
+        approvals: vec![info.sender.clone()],
+        expires: env.block.time.plus_seconds(PROPOSAL_DURATION),
+    };
+
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_proposal")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("expires", proposal.expires.to_string()))
+}
+
+pub fn run_vote(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    proposal_id: u64,
+) -> ContractResponse {
+    let mut proposal = load_open_proposal(deps.as_ref(), env, proposal_id)?;
+
+    if proposal.approvals.contains(&info.sender) {
+        return Err(ContractError::AlredyVoted {
+            addr: info.sender.clone(),
+            proposal_id,
+        });
+    }
+
+    proposal.approvals.push(info.sender.clone());
+
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "vote")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("voter", info.sender.to_string()))
+}
+
+/// Remove an approved proposal from the storage, returning it in order to be applied
+pub fn take_approved_proposal(
+    deps: DepsMut,
+    env: &Env,
+    proposal_id: u64,
+) -> ContractResult<Proposal> {
+    let config = CONFIG.load(deps.storage)?;
+    let proposal = load_open_proposal(deps.as_ref(), env, proposal_id)?;
+
+    let approvals = proposal
+        .approvals
+        .iter()
+        .filter(|addr| config.owners.contains(addr))
+        .count() as u64;
+
+    if approvals < config.threshold {
+        return Err(ContractError::ThresholdNotReached {
+            proposal_id,
+            approvals,
+            threshold: config.threshold,
+        });
+    }
+
+    PROPOSALS.remove(deps.storage, proposal_id);
+
+    Ok(proposal)
+}
+
+pub fn run_close_proposal(deps: DepsMut, env: &Env, proposal_id: u64) -> ContractResponse {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, proposal_id)?
+        .ok_or(ContractError::ProposalNotFound { proposal_id })?;
+
+    if !proposal.is_expired(env.block.time) {
+        return Err(ContractError::ProposalNotExpired { proposal_id });
+    }
+
+    PROPOSALS.remove(deps.storage, proposal_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "close_proposal")
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+fn load_open_proposal(deps: Deps, env: &Env, proposal_id: u64) -> ContractResult<Proposal> {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, proposal_id)?
+        .ok_or(ContractError::ProposalNotFound { proposal_id })?;
+
+    if proposal.is_expired(env.block.time) {
+        return Err(ContractError::ProposalExpired { proposal_id });
+    }
+
+    Ok(proposal)
+}
+
+pub fn run_set_visibility(deps: DepsMut, msg: SetVisibilityMsg) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    assert_namespace(deps.as_ref(), &key.0)?;
+
+    if msg.public {
+        PRIVATE_KEYS.remove(deps.storage, key.clone());
+    } else {
+        PRIVATE_KEYS.save(deps.storage, key.clone(), &())?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_visibility")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", msg.key)
+        .add_attribute("public", msg.public.to_string()))
+}
+
+pub fn run_read_variables(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    keys: Vec<String>,
+) -> ContractResponse {
+    let variables = keys
+        .into_iter()
+        .map(|key| {
+            let storage_key = variable_key(namespace.clone(), &key);
+            let variable = may_load_variable(deps.storage, &env.block, storage_key.clone())?
+                .ok_or(ContractError::KeyNotFound { key: key.clone() })?;
+            Ok((key, resolve_reference(deps, env, storage_key, variable)?))
+        })
+        .collect::<ContractResult<BTreeMap<String, Variable>>>()?;
+
+    Ok(Response::new()
+        .add_attribute("action", "read_variables")
+        .set_data(variables.into_binary()?))
+}
+
+pub fn run_set_schema(deps: DepsMut, msg: SetSchemaMsg) -> ContractResponse {
+    let key = variable_key(msg.namespace, &msg.key);
+
+    assert_namespace(deps.as_ref(), &key.0)?;
+
+    match &msg.schema {
+        Some(schema) => {
+            schema.validate()?;
+
+            if let Some(variable) = VARIABLES.may_load(deps.storage, key.clone())? {
+                schema.validate_variable(&variable).map_err(|err| {
+                    ContractError::SchemaViolation {
+                        key: msg.key.clone(),
+                        reason: err.to_string(),
+                    }
+                })?;
+            }
+
+            SCHEMAS.save(deps.storage, key.clone(), schema)?
+        }
+        None => SCHEMAS.remove(deps.storage, key.clone()),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_schema")
+        .add_attribute("namespace", key.0)
+        .add_attribute("key", msg.key)
+        .add_attribute(
+            "schema",
+            msg.schema
+                .map(|schema| format!("{schema:?}"))
+                .unwrap_or("none".to_string()),
+        ))
+}
+
+pub fn run_create_namespace(deps: DepsMut, msg: CreateNamespaceMsg) -> ContractResponse {
+    if msg.namespace == DEFAULT_NAMESPACE || NAMESPACES.has(deps.storage, msg.namespace.clone()) {
+        return Err(ContractError::NamespaceAlredyExists {
+            namespace: msg.namespace,
+        });
+    }
+
+    let namespace = Namespace {
+        owners: msg
+            .owners
+            .into_iter()
+            .map(|owner| owner.into_addr(deps.api))
+            .collect::<StdResult<Vec<Addr>>>()?,
+    };
+
+    NAMESPACES.save(deps.storage, msg.namespace.clone(), &namespace)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_namespace")
+        .add_attribute("namespace", msg.namespace)
+        .add_attribute("owners", format!("{:?}", namespace.owners)))
+}
+
+pub fn run_delete_namespace(deps: DepsMut, namespace: String) -> ContractResponse {
+    if !NAMESPACES.has(deps.storage, namespace.clone()) {
+        return Err(ContractError::NamespaceNotFound { namespace });
+    }
+
+    if VARIABLES
+        .prefix(namespace.clone())
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+    {
+        return Err(ContractError::NamespaceNotEmpty { namespace });
+    }
+
+    NAMESPACES.remove(deps.storage, namespace.clone());
+
+    // A namespace created again with the same name must not inherit the previous configuration
+    clear_namespace(deps.storage, &SCHEMAS, &namespace)?;
+    clear_namespace(deps.storage, &TIMELOCKS, &namespace)?;
+    clear_namespace(deps.storage, &PENDING_TIMELOCKS, &namespace)?;
+    clear_namespace(deps.storage, &PENDING_UPDATES, &namespace)?;
+    clear_namespace(deps.storage, &PRIVATE_KEYS, &namespace)?;
+
+    for (prefix, contract) in SUBSCRIPTIONS
+        .sub_prefix(namespace.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+    {
+        SUBSCRIPTIONS.remove(deps.storage, (namespace.clone(), prefix, &contract));
+    }
+
+    for (address, mut grants) in ROLES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+    {
+        if grants.iter().any(|grant| grant.namespace == namespace) {
+            grants.retain(|grant| grant.namespace != namespace);
+
+            if grants.is_empty() {
+                ROLES.remove(deps.storage, &address);
+            } else {
+                ROLES.save(deps.storage, &address, &grants)?;
+            }
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "delete_namespace")
+        .add_attribute("namespace", namespace))
+}
+
+pub fn run_update_namespace_owners(
+    deps: DepsMut,
+    msg: UpdateNamespaceOwnersMsg,
+) -> ContractResponse {
+    let mut namespace = NAMESPACES
+        .may_load(deps.storage, msg.namespace.clone())?
+        .ok_or(ContractError::NamespaceNotFound {
+            namespace: msg.namespace.clone(),
+        })?;
+
+    let mut attrs = vec![];
+
+    for address in msg.add.unwrap_or_default() {
+        let address = address.into_addr(deps.api)?;
+
+        if namespace.is_owner(&address) {
+            return Err(ContractError::IsAlredyOwner { addr: address });
+        }
+
+        attrs.push(attr("owner_added", address.clone()));
+        namespace.owners.push(address);
+    }
+
+    for address in msg.remove.unwrap_or_default() {
+        let address = address.into_addr(deps.api)?;
+
+        if !namespace.is_owner(&address) {
+            return Err(ContractError::IsNotOwner { addr: address });
+        }
+
+        attrs.push(attr("owner_removed", address.clone()));
+        namespace.owners.retain(|owner| owner != address);
+    }
+
+    if attrs.is_empty() {
+        return Err(ContractError::InvalidUpdateOwnerMsg);
+    }
+
+    NAMESPACES.save(deps.storage, msg.namespace.clone(), &namespace)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_namespace_owners")
+        .add_attribute("namespace", msg.namespace)
+        .add_attributes(attrs))
+}
+
+pub fn run_subscribe(deps: DepsMut, msg: SubscribeMsg) -> ContractResponse {
+    let namespace = msg.namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+    let contract = msg.contract.into_addr(deps.api)?;
+
+    assert_namespace(deps.as_ref(), &namespace)?;
+
+    SUBSCRIPTIONS.save(
+        deps.storage,
+        (namespace.clone(), msg.prefix.clone(), &contract),
+        &msg.on_error,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "subscribe")
+        .add_attribute("namespace", namespace)
+        .add_attribute("prefix", format!("{}*", msg.prefix))
+        .add_attribute("contract", contract)
+        .add_attribute("on_error", format!("{:?}", msg.on_error)))
+}
+
+pub fn run_unsubscribe(deps: DepsMut, msg: UnsubscribeMsg) -> ContractResponse {
+    let namespace = msg.namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+    let contract = msg.contract.into_addr(deps.api)?;
+    let key = (namespace.clone(), msg.prefix.clone(), &contract);
+
+    if !SUBSCRIPTIONS.has(deps.storage, key.clone()) {
+        return Err(ContractError::SubscriptionNotFound {
+            contract,
+            prefix: msg.prefix,
+        });
+    }
+
+    SUBSCRIPTIONS.remove(deps.storage, key);
+
+    Ok(Response::new()
+        .add_attribute("action", "unsubscribe")
+        .add_attribute("namespace", namespace)
+        .add_attribute("prefix", format!("{}*", msg.prefix))
+        .add_attribute("contract", contract))
+}
+
+fn assert_namespace(deps: Deps, namespace: &str) -> ContractResult<()> {
+    if namespace != DEFAULT_NAMESPACE && !NAMESPACES.has(deps.storage, namespace.to_string()) {
+        return Err(ContractError::NamespaceNotFound {
+            namespace: namespace.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn assert_schema(deps: Deps, key: &VariableKey, variable: &Variable) -> ContractResult<()> {
+    if let Some(schema) = SCHEMAS.may_load(deps.storage, key.clone())? {
+        schema
+            .validate_variable(variable)
+            .map_err(|err| ContractError::SchemaViolation {
+                key: key.1.clone(),
+                reason: err.to_string(),
+            })?;
+    }
+
+    Ok(())
+}
+
+fn assert_not_timelocked(deps: Deps, key: &VariableKey) -> ContractResult<()> {
+    if TIMELOCKS.has(deps.storage, key.clone()) {
+        return Err(ContractError::KeyTimelocked { key: key.1.clone() });
+    }
+
+    Ok(())
+}
+
+/// Remove every entry of `map` in `namespace`
+fn clear_namespace<T: Serialize + DeserializeOwned>(
+    storage: &mut dyn Storage,
+    map: &Map<VariableKey, T>,
+    namespace: &str,
+) -> StdResult<()> {
+    for key in map
+        .prefix(namespace.to_string())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?
+    {
+        map.remove(storage, (namespace.to_string(), key));
+    }
+
+    Ok(())
+}
+
+/// Block time after `delay` seconds. Delays longer than `MAX_TIMELOCK_DELAY`, stored before the limit, are capped
+fn time_after(env: &Env, delay: u64) -> StdResult<Timestamp> {
+    let nanos = Uint64::new(delay.min(MAX_TIMELOCK_DELAY))
+        .checked_mul(Uint64::new(1_000_000_000))?
+        .checked_add(Uint64::new(env.block.time.nanos()))?;
+
+    Ok(Timestamp::from_nanos(nanos.u64()))
+}
+
+/// `true` if `key` can not be removed because timelocked or frozen
+fn is_locked(deps: Deps, env: &Env, key: &VariableKey) -> StdResult<bool> {
+    Ok(TIMELOCKS.has(deps.storage, key.clone())
+        || FROZEN
+            .may_load(deps.storage, key.clone())?
+            .is_some_and(|until| !until.is_expired(&env.block)))
+}
+
+fn assert_not_frozen_forever(deps: Deps, key: &VariableKey) -> ContractResult<()> {
+    match FROZEN.may_load(deps.storage, key.clone())? {
+        Some(Expiration::Never {}) => Err(ContractError::KeyFrozen {
+            key: key.1.clone(),
+            until: Expiration::Never {},
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn assert_not_frozen(deps: Deps, env: &Env, key: &VariableKey) -> ContractResult<()> {
+    match FROZEN.may_load(deps.storage, key.clone())? {
+        Some(until) if !until.is_expired(&env.block) => Err(ContractError::KeyFrozen {
+            key: key.1.clone(),
+            until,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Nominate `addresses` as owners. A pending nomination of the same address is replaced
+fn nominate_owners(
+    deps: DepsMut,
+    config: &Config,
+    addresses: Vec<String>,
+    expires_at: Expiration,
+) -> ContractResult<Vec<Attribute>> {
+    let mut attrs: Vec<Attribute> = vec![];
+
+    for address in addresses {
+        let address = address.into_addr(deps.api)?;
+
+        if config.owners.contains(&address) {
+            return Err(ContractError::IsAlredyOwner { addr: address });
+        }
+
+        NOMINATIONS.save(deps.storage, &address, &expires_at)?;
+
+        attrs.push(attr("owner_nominated", address));
+    }
+
+    Ok(attrs)
+}
+
+fn remove_owners(
+    deps: Deps,
+    config: &mut Config,
+    addresses: Vec<String>,
+) -> ContractResult<Vec<Attribute>> {
+    let mut attrs: Vec<Attribute> = vec![];
+
+    let addresses = addresses
+        .into_iter()
+        .map(|address| -> ContractResult<Addr> {
+            let address = address.into_addr(deps.api)?;
+
+            if !config.owners.contains(&address) {
+                return Err(ContractError::IsNotOwner { addr: address });
+            }
+
+            attrs.push(attr("owner_removed", address.clone()));
+
+            Ok(address)
+        })
+        .collect::<ContractResult<Vec<Addr>>>()?;
+
+    config.owners.retain_mut(|addr| !addresses.contains(addr));
+
+    Ok(attrs)
+}
+
+fn assert_expiration(env: &Env, key: &str, expires_at: Option<Expiration>) -> ContractResult<()> {
+    match expires_at {
+        Some(expires_at) if expires_at.is_expired(&env.block) => {
+            Err(ContractError::ExpirationReached {
+                key: key.to_string(),
+                expires_at,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Replace the expiration of `key`. `Expiration::Never` removes it
+fn set_expiration(
+    storage: &mut dyn Storage,
+    key: &VariableKey,
+    expiration: Expiration,
+) -> StdResult<()> {
+    if let Some(current) = EXPIRATIONS.may_load(storage, key.clone())? {
+        if let Some((index, at)) = expiration_index(&current) {
+            index.remove(storage, (at, key.0.clone(), key.1.clone()));
+        }
+        EXPIRATIONS.remove(storage, key.clone());
+    }
+
+    if let Some((index, at)) = expiration_index(&expiration) {
+        index.save(storage, (at, key.0.clone(), key.1.clone()), &())?;
+        EXPIRATIONS.save(storage, key.clone(), &expiration)?;
+    }
+
+    Ok(())
+}
+
+/// Write (or remove if `value` is `None`) a variable, recording the change in the key history.
+///
+/// Return the hooks to send to the subscribers of `key` on update and remove, and the event of the change
+fn store_variable(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    key: &VariableKey,
+    value: Option<Variable>,
+    action: VariableAction,
+) -> StdResult<(Vec<SubMsg>, Event)> {
+    let (hooks, old) = match action {
+        // A key expired but not pruned keeps the state of its previous value
+        VariableAction::Register => {
+            clear_key_state(storage, key);
+            (vec![], None)
+        }
+        VariableAction::Update | VariableAction::Remove => {
+            let old = VARIABLES
+                .may_load(storage, key.clone())?
+                .map(|old| active_value(storage, &env.block, key.clone(), old))
+                .transpose()?;
+            (
+                variable_changed_hooks(storage, key, old.clone(), value.clone())?,
+                old,
+            )
+        }
+    };
+
+    let event = variable_event(storage, key, sender, &action, old.as_ref(), value.as_ref())?;
+
+    let modification = Modification {
+        height: env.block.height,
+        time: env.block.time,
+        sender: sender.clone(),
+    };
+
+    match &value {
+        Some(value) => {
+            VARIABLES.save(storage, key.clone(), value, env.block.height)?;
+
+            let metadata = match METADATA.may_load(storage, key.clone())? {
+                Some(metadata) if action == VariableAction::Update => VariableMetadata {
+                    updated: modification,
+                    ..metadata
+                },
+                old => {
+                    index_tags(
+                        storage,
+                        key,
+                        &old.map(|old| old.tags).unwrap_or_default(),
+                        &[],
+                    )?;
+                    VariableMetadata {
+                        description: None,
+                        tags: vec![],
+                        created: modification.clone(),
+                        updated: modification,
+                    }
+                }
+            };
+            METADATA.save(storage, key.clone(), &metadata)?;
+
+            // Activated values are replaced by the new one
+            if let Some(mut schedule) = SCHEDULES.may_load(storage, key.clone())? {
+                schedule.retain(|scheduled| !scheduled.activates_at.is_triggered(&env.block));
+                SCHEDULES.save(storage, key.clone(), &schedule)?;
+            }
+        }
+        None => {
+            VARIABLES.remove(storage, key.clone(), env.block.height)?;
+            clear_key_state(storage, key);
+            set_expiration(storage, key, Expiration::Never {})?;
+
+            if let Some(metadata) = METADATA.may_load(storage, key.clone())? {
+                index_tags(storage, key, &metadata.tags, &[])?;
+                METADATA.remove(storage, key.clone());
+            }
+        }
+    }
+
+    let id = HISTORY_COUNTER.may_load(storage)?.unwrap_or_default() + 1;
+    HISTORY_COUNTER.save(storage, &id)?;
+
+    HISTORY.save(
+        storage,
+        (key.0.clone(), key.1.clone(), id),
+        &VariableHistoryEntry {
+            action,
+            value,
+            height: env.block.height,
+            time: env.block.time,
+            sender: sender.clone(),
+        },
+    )?;
+
+    Ok((hooks, event))
+}
+
+/// `variable` as attribute value, redacted if `key` is private
+fn display_value(storage: &dyn Storage, key: &VariableKey, variable: &Variable) -> String {
+    if PRIVATE_KEYS.has(storage, key.clone()) {
+        PRIVATE_VALUE.to_string()
+    } else {
+        variable.to_string()
+    }
+}
+
+/// JSON encoded `variable` as event attribute value, redacted if `key` is private
+fn json_value(
+    storage: &dyn Storage,
+    key: &VariableKey,
+    variable: Option<&Variable>,
+) -> StdResult<String> {
+    match variable {
+        Some(_) if PRIVATE_KEYS.has(storage, key.clone()) => Ok(PRIVATE_VALUE.to_string()),
+        variable => to_json_string(&variable),
+    }
+}
+
+/// Remove the state bound to the value of `key`: scheduled values, freeze, timelock and pending changes
+fn clear_key_state(storage: &mut dyn Storage, key: &VariableKey) {
+    SCHEDULES.remove(storage, key.clone());
+    FROZEN.remove(storage, key.clone());
+    TIMELOCKS.remove(storage, key.clone());
+    PENDING_TIMELOCKS.remove(storage, key.clone());
+    PENDING_UPDATES.remove(storage, key.clone());
+}
+
+/// `variable_registered`, `variable_updated` or `variable_removed` event, with one event per key.
+/// `old_value` and `new_value` are JSON encoded, `null` if missing and `PRIVATE_VALUE` if `key` is private
+fn variable_event(
+    storage: &dyn Storage,
+    key: &VariableKey,
+    sender: &Addr,
+    action: &VariableAction,
+    old: Option<&Variable>,
+    new: Option<&Variable>,
+) -> StdResult<Event> {
+    let ty = match action {
+        VariableAction::Register => "variable_registered",
+        VariableAction::Update => "variable_updated",
+        VariableAction::Remove => "variable_removed",
+    };
+
+    Ok(Event::new(ty)
+        .add_attribute("namespace", &key.0)
+        .add_attribute("key", &key.1)
+        .add_attribute("kind", new.or(old).map_or("none", Variable::kind))
+        .add_attribute("old_value", json_value(storage, key, old)?)
+        .add_attribute("new_value", json_value(storage, key, new)?)
+        .add_attribute("sender", sender))
+}
+
+/// `owners_updated` event, with the JSON encoded owners before and after the change
+fn owners_event(old: &Config, new: &Config) -> StdResult<Event> {
+    Ok(Event::new("owners_updated")
+        .add_attribute("old_owners", to_json_string(&old.owners)?)
+        .add_attribute("new_owners", to_json_string(&new.owners)?)
+        .add_attribute("threshold", new.threshold.to_string()))
+}
+
+/// Move the `TAGS` entries of `key` from `old` to `new`
+fn index_tags(
+    storage: &mut dyn Storage,
+    key: &VariableKey,
+    old: &[String],
+    new: &[String],
+) -> StdResult<()> {
+    for tag in old {
+        TAGS.remove(storage, (key.0.clone(), tag.clone(), key.1.clone()));
+    }
+
+    for tag in new {
+        TAGS.save(storage, (key.0.clone(), tag.clone(), key.1.clone()), &())?;
+    }
+
+    Ok(())
+}
+
+/// Build a `HookMsg` for every contract subscribed to a prefix of `key`
+fn variable_changed_hooks(
+    storage: &dyn Storage,
+    key: &VariableKey,
+    old: Option<Variable>,
+    new: Option<Variable>,
+) -> StdResult<Vec<SubMsg>> {
+    let msg = HookMsg::VariableChangedHook(VariableChangedHook {
+        namespace: key.0.clone(),
+        key: key.1.clone(),
+        old,
+        new,
+    })
+    .into_binary()?;
+
+    let mut hooks = vec![];
+
+    for end in key
+        .1
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(once(key.1.len()))
+    {
+        for subscription in SUBSCRIPTIONS
+            .prefix((key.0.clone(), key.1[..end].to_string()))
+            .range(storage, None, None, Order::Ascending)
+        {
+            let (contract, on_error) = subscription?;
+
+            let wasm_msg = WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: msg.clone(),
+                funds: vec![],
+            };
+
+            hooks.push(match on_error {
+                HookFailurePolicy::Ignore => SubMsg::reply_on_error(wasm_msg, HOOK_REPLY_ID),
+                HookFailurePolicy::Revert => SubMsg::new(wasm_msg),
+            });
+        }
+    }
+
+    Ok(hooks)
 }
@@ -1,6 +1,8 @@
 #[cfg(not(feature = "library"))]
 pub mod contract;
 mod execute;
+mod migration;
+mod permission;
 mod query;
 mod response;
 mod state;
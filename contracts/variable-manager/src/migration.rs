@@ -0,0 +1,99 @@
+use cosmwasm_std::{DepsMut, Env, Order, StdResult, Storage};
+use cw2::{get_contract_version, set_contract_version, CONTRACT};
+use semver::Version;
+use variable_manager_pkg::definitions::{Config, DEFAULT_MAX_REFERENCE_DEPTH, DEFAULT_NAMESPACE};
+
+use crate::{
+    response::{ContractError, ContractResult},
+    state::{CONFIG, VARIABLES},
+};
+
+pub const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Storage layout of version 1.0.4, before namespaces and multisig
+mod legacy {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Addr;
+    use cw_storage_plus::{Item, Map};
+    use variable_manager_pkg::definitions::Variable;
+
+    #[cw_serde]
+    pub struct Config {
+        pub owners: Vec<Addr>,
+    }
+
+    pub const CONFIG: Item<Config> = Item::new("config_key");
+
+    pub const VARIABLES: Map<String, Variable> = Map::new("variables_key");
+}
+
+/// Check the stored cw2 version and set the current one. Return the version migrated from
+pub fn assert_and_set_version(
+    deps: DepsMut,
+    migrate_storage: bool,
+) -> ContractResult<Option<String>> {
+    let stored = match CONTRACT.may_load(deps.storage)? {
+        Some(_) if migrate_storage => return Err(ContractError::StorageAlreadyMigrated),
+        Some(_) => get_contract_version(deps.storage)?,
+        None if migrate_storage => {
+            set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+            return Ok(None);
+        }
+        None => return Err(ContractError::StorageMigrationRequired),
+    };
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {
+            contract: stored.contract,
+        });
+    }
+
+    if stored.version.parse::<Version>()? > CONTRACT_VERSION.parse::<Version>()? {
+        return Err(ContractError::InvalidMigrationVersion {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Some(stored.version))
+}
+
+/// Move `CONFIG` and `VARIABLES` from the 1.0.4 layout. Variables are moved in the default namespace.
+/// Return the amount of variables migrated
+pub fn migrate_legacy_storage(storage: &mut dyn Storage, env: &Env) -> StdResult<usize> {
+    let config = legacy::CONFIG.load(storage)?;
+
+    CONFIG.save(
+        storage,
+        &Config {
+            owners: config.owners,
+            threshold: 1,
+            guardian: None,
+            paused: false,
+            max_reference_depth: DEFAULT_MAX_REFERENCE_DEPTH,
+        },
+    )?;
+
+    // Old and new keys share the same namespace, so every old key is removed before saving the new ones
+    let variables = legacy::VARIABLES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (key, _) in &variables {
+        legacy::VARIABLES.remove(storage, key.clone());
+    }
+
+    for (key, variable) in &variables {
+        VARIABLES.save(
+            storage,
+            (DEFAULT_NAMESPACE.to_string(), key.clone()),
+            variable,
+            env.block.height,
+        )?;
+    }
+
+    Ok(variables.len())
+}
@@ -0,0 +1,248 @@
+use cosmwasm_std::{Addr, Deps};
+use variable_manager_pkg::{
+    definitions::{Role, RoleGrant, DEFAULT_NAMESPACE},
+    msgs::{ExecuteMsg, Operation, SubscribeMsg, UnsubscribeMsg},
+};
+
+use crate::{
+    response::{ContractError, ContractResult},
+    state::{variable_key, CONFIG, NAMESPACES, PRIVATE_KEYS, ROLES},
+};
+
+pub enum Authorization {
+    /// `msg` can be applied immediately
+    Direct,
+    /// `msg` is sent by an owner and is subject to `Config::threshold`
+    Owner,
+}
+
+/// Check if `sender` is allowed to execute `msg`.
+///
+/// Namespace owners and addresses with a `Role` covering every key touched by `msg` act directly, otherwise `sender` must be an owner
+pub fn authorize(deps: Deps, sender: &Addr, msg: &ExecuteMsg) -> ContractResult<Authorization> {
+    let config = CONFIG.load(deps.storage)?;
+
+    match msg {
+        ExecuteMsg::ExecutePending { .. }
+        | ExecuteMsg::ExecuteProposal { .. }
+        | ExecuteMsg::CloseProposal { .. }
+        | ExecuteMsg::PruneExpired { .. }
+        | ExecuteMsg::AcceptOwnership {} => return Ok(Authorization::Direct),
+        ExecuteMsg::Vote { .. } => {
+            config.validate_owner(sender)?;
+            return Ok(Authorization::Direct);
+        }
+        ExecuteMsg::UpdateOwners(_)
+        | ExecuteMsg::SetGuardian { .. }
+        | ExecuteMsg::SetMaxReferenceDepth { .. }
+        | ExecuteMsg::NominateOwner { .. }
+        | ExecuteMsg::RevokeNomination { .. }
+        | ExecuteMsg::CreateNamespace(_)
+        | ExecuteMsg::DeleteNamespace { .. } => {
+            config.validate_owner(sender)?;
+            return Ok(Authorization::Owner);
+        }
+        ExecuteMsg::ReadVariables { namespace, keys } => {
+            if !config.owners.contains(sender) {
+                let grants = ROLES.may_load(deps.storage, sender)?.unwrap_or_default();
+                let readers = &[Role::Admin, Role::Writer, Role::Reader];
+
+                for key in keys {
+                    if PRIVATE_KEYS.has(deps.storage, variable_key(namespace.clone(), key)) {
+                        assert_role(deps, &grants, sender, readers, namespace, key).map_err(
+                            |_| ContractError::PrivateKey {
+                                addr: sender.clone(),
+                                key: key.clone(),
+                            },
+                        )?;
+                    }
+                }
+            }
+            return Ok(Authorization::Direct);
+        }
+        ExecuteMsg::Pause {} | ExecuteMsg::Unpause {} => {
+            if config.guardian.as_ref() != Some(sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+            return Ok(Authorization::Direct);
+        }
+        _ => {}
+    }
+
+    let grants = ROLES.may_load(deps.storage, sender)?.unwrap_or_default();
+
+    match assert_grants(deps, &grants, sender, msg) {
+        Ok(()) => Ok(Authorization::Direct),
+        Err(_) if config.owners.contains(sender) => Ok(Authorization::Owner),
+        Err(err) => Err(err),
+    }
+}
+
+fn assert_grants(
+    deps: Deps,
+    grants: &[RoleGrant],
+    sender: &Addr,
+    msg: &ExecuteMsg,
+) -> ContractResult<()> {
+    let writers = &[Role::Admin, Role::Writer];
+
+    match msg {
+        ExecuteMsg::RegisterVariable(msg)
+        | ExecuteMsg::UpdateVariable(msg)
+        | ExecuteMsg::Upsert(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }
+        ExecuteMsg::RegisterVariables(msgs) | ExecuteMsg::UpdateVariables(msgs) => {
+            msgs.iter().try_for_each(|msg| {
+                assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+            })
+        }
+        ExecuteMsg::RemoveVariable(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }
+        ExecuteMsg::RemoveVariables(msgs) => msgs.iter().try_for_each(|msg| {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }),
+        ExecuteMsg::RemoveByPrefix(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.prefix)
+        }
+        ExecuteMsg::ScheduleVariable(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }
+        ExecuteMsg::SetMetadata(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }
+        ExecuteMsg::CancelScheduled { namespace, key, .. } => {
+            assert_role(deps, grants, sender, writers, namespace, key)
+        }
+        ExecuteMsg::CompareAndSwap(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }
+        ExecuteMsg::Batch(operations) => {
+            operations.iter().try_for_each(|operation| match operation {
+                Operation::Register(msg) | Operation::Update(msg) | Operation::Upsert(msg) => {
+                    assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+                }
+                Operation::Remove(msg) => {
+                    assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+                }
+            })
+        }
+        ExecuteMsg::SetTimelock(msg) => assert_role(
+            deps,
+            grants,
+            sender,
+            &[Role::Admin],
+            &msg.namespace,
+            &msg.key,
+        ),
+        ExecuteMsg::ProposeUpdate(msg) => {
+            assert_role(deps, grants, sender, writers, &msg.namespace, &msg.key)
+        }
+        ExecuteMsg::CancelPending { namespace, key } => {
+            assert_role(deps, grants, sender, writers, namespace, key)
+        }
+        ExecuteMsg::FreezeVariable { namespace, key, .. }
+        | ExecuteMsg::UnfreezeVariable { namespace, key } => {
+            assert_role(deps, grants, sender, &[Role::Admin], namespace, key)
+        }
+        ExecuteMsg::SetVisibility(msg) => assert_role(
+            deps,
+            grants,
+            sender,
+            &[Role::Admin],
+            &msg.namespace,
+            &msg.key,
+        ),
+        ExecuteMsg::SetSchema(msg) => assert_role(
+            deps,
+            grants,
+            sender,
+            &[Role::Admin],
+            &msg.namespace,
+            &msg.key,
+        ),
+        ExecuteMsg::GrantRole(msg) | ExecuteMsg::RevokeRole(msg) => assert_role(
+            deps,
+            grants,
+            sender,
+            &[Role::Admin],
+            &msg.namespace,
+            &msg.prefix,
+        ),
+        ExecuteMsg::Subscribe(SubscribeMsg {
+            namespace, prefix, ..
+        })
+        | ExecuteMsg::Unsubscribe(UnsubscribeMsg {
+            namespace, prefix, ..
+        }) => assert_role(deps, grants, sender, &[Role::Admin], namespace, prefix),
+        ExecuteMsg::UpdateNamespaceOwners(msg) => {
+            if is_namespace_owner(deps, &msg.namespace, sender)? {
+                Ok(())
+            } else {
+                Err(ContractError::Unauthorized {})
+            }
+        }
+        ExecuteMsg::UpdateOwners(_)
+        | ExecuteMsg::SetGuardian { .. }
+        | ExecuteMsg::SetMaxReferenceDepth { .. }
+        | ExecuteMsg::NominateOwner { .. }
+        | ExecuteMsg::RevokeNomination { .. }
+        | ExecuteMsg::AcceptOwnership {}
+        | ExecuteMsg::ReadVariables { .. }
+        | ExecuteMsg::Pause {}
+        | ExecuteMsg::Unpause {}
+        | ExecuteMsg::ExecutePending { .. }
+        | ExecuteMsg::Vote { .. }
+        | ExecuteMsg::ExecuteProposal { .. }
+        | ExecuteMsg::CloseProposal { .. }
+        | ExecuteMsg::PruneExpired { .. }
+        | ExecuteMsg::CreateNamespace(_)
+        | ExecuteMsg::DeleteNamespace { .. } => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// While the contract is paused only `Unpause`, `SetGuardian`, `ReadVariables` and the messages needed to approve a `SetGuardian` proposal are accepted
+pub fn assert_not_paused(deps: Deps, msg: &ExecuteMsg) -> ContractResult<()> {
+    match msg {
+        ExecuteMsg::Unpause {}
+        | ExecuteMsg::ReadVariables { .. }
+        | ExecuteMsg::SetGuardian { .. }
+        | ExecuteMsg::Vote { .. }
+        | ExecuteMsg::ExecuteProposal { .. }
+        | ExecuteMsg::CloseProposal { .. } => Ok(()),
+        _ if CONFIG.load(deps.storage)?.paused => Err(ContractError::Paused {}),
+        _ => Ok(()),
+    }
+}
+
+fn assert_role(
+    deps: Deps,
+    grants: &[RoleGrant],
+    sender: &Addr,
+    roles: &[Role],
+    namespace: &Option<String>,
+    key: &str,
+) -> ContractResult<()> {
+    let namespace = namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+
+    if grants
+        .iter()
+        .any(|grant| roles.contains(&grant.role) && grant.covers(namespace, key))
+        || is_namespace_owner(deps, namespace, sender)?
+    {
+        Ok(())
+    } else {
+        Err(ContractError::MissingRole {
+            addr: sender.clone(),
+            key: key.to_string(),
+        })
+    }
+}
+
+fn is_namespace_owner(deps: Deps, namespace: &str, sender: &Addr) -> ContractResult<bool> {
+    Ok(NAMESPACES
+        .may_load(deps.storage, namespace.to_string())?
+        .map(|namespace| namespace.is_owner(sender))
+        .unwrap_or_default())
+}
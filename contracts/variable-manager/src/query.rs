@@ -1,39 +1,740 @@
 use std::collections::BTreeMap;
 
-use cosmwasm_std::{Deps, StdError, StdResult};
-use variable_manager_pkg::definitions::Variable;
+use cosmwasm_std::{Addr, Deps, Env, Order, StdError, StdResult, Timestamp};
+use cw_storage_plus::Bound;
+use cw_utils::{Duration, Expiration};
+use rhaki_cw_plus::{storage::StorageOrder, traits::IntoAddr};
+use variable_manager_pkg::{
+    definitions::{
+        ExpiringVariable, Namespace, PendingTimelock, PendingUpdate, Proposal, RoleGrant,
+        ScheduledValue, Subscription, Variable, VariableHistoryEntry, VariableSchema,
+        VariableWithMeta, DEFAULT_NAMESPACE,
+    },
+    msgs::{ExecuteMsg, Operation, QueryMsg},
+};
 
-use crate::state::VARIABLES;
+use crate::state::{
+    active_value, expiration_index, is_expired, may_load_variable, variable_key, VariableKey,
+    CONFIG, EXPIRING_AT_HEIGHT, EXPIRING_AT_TIME, FROZEN, HISTORY, METADATA, NAMESPACES,
+    NOMINATIONS, PENDING_TIMELOCKS, PENDING_UPDATES, PRIVATE_KEYS, PROPOSALS, ROLES, SCHEDULES,
+    SCHEMAS, SUBSCRIPTIONS, TAGS, TIMELOCKS, VARIABLES,
+};
 
-pub fn qy_get_variable(deps: Deps, key: String) -> StdResult<Variable> {
-    VARIABLES
-        .load(deps.storage, key.clone())
-        .map_err(|_| StdError::generic_err(format!("variable not found - key: {key}")))
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+type VariableItem = StdResult<(String, Variable)>;
+
+pub fn qy_get_variable(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<Variable> {
+    let variable = qy_get_variable_raw(deps, env, namespace.clone(), key.clone())?;
+
+    resolve_reference(deps, env, variable_key(namespace, &key), variable)
+}
+
+pub fn qy_get_variable_raw(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<Variable> {
+    let storage_key = variable_key(namespace, &key);
+
+    assert_public(deps, &storage_key)?;
+
+    may_load_variable(deps.storage, &env.block, storage_key)?
+        .ok_or_else(|| StdError::generic_err(format!("variable not found - key: {key}")))
 }
 
-pub fn qy_get_variables(deps: Deps, keys: Vec<String>) -> StdResult<BTreeMap<String, Variable>> {
+/// Follow `variable` while it is a `Variable::Reference`, up to `Config::max_reference_depth` references.
+///
+/// References to another manager are loaded with `QueryMsg::GetVariableRaw`, so the whole chain is resolved here
+pub fn resolve_reference(
+    deps: Deps,
+    env: &Env,
+    key: VariableKey,
+    mut variable: Variable,
+) -> StdResult<Variable> {
+    let max_depth = CONFIG.load(deps.storage)?.max_reference_depth;
+
+    let mut manager = env.contract.address.clone();
+    let mut visited = vec![(manager.clone(), key)];
+
+    while let Variable::Reference(reference) = variable {
+        if visited.len() > max_depth as usize {
+            return Err(StdError::generic_err(format!(
+                "reference depth exceeded - max depth: {max_depth}"
+            )));
+        }
+
+        manager = reference.manager.unwrap_or(manager);
+        let key = variable_key(reference.namespace, &reference.key);
+
+        if visited.contains(&(manager.clone(), key.clone())) {
+            return Err(StdError::generic_err(format!(
+                "reference cycle detected - key: {}",
+                key.1
+            )));
+        }
+
+        variable = if manager == env.contract.address {
+            qy_get_variable_raw(deps, env, Some(key.0.clone()), key.1.clone())?
+        } else {
+            deps.querier.query_wasm_smart(
+                &manager,
+                &QueryMsg::GetVariableRaw {
+                    namespace: Some(key.0.clone()),
+                    key: key.1.clone(),
+                },
+            )?
+        };
+
+        visited.push((manager.clone(), key));
+    }
+
+    Ok(variable)
+}
+
+pub fn qy_get_variables(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    keys: Vec<String>,
+    allow_missing: bool,
+) -> StdResult<BTreeMap<String, Variable>> {
     keys.into_iter()
-        .map(|key| {
-            Ok((
-                key.clone(),
-                VARIABLES.load(deps.storage, key.clone()).map_err(|_| {
-                    StdError::generic_err(format!("Variable not found - key: {key}"))
-                })?,
-            ))
+        .filter_map(|key| {
+            let storage_key = variable_key(namespace.clone(), &key);
+
+            if let Err(err) = assert_public(deps, &storage_key) {
+                return Some(Err(err));
+            }
+
+            match may_load_variable(deps.storage, &env.block, storage_key.clone()) {
+                Ok(Some(variable)) => Some(
+                    resolve_reference(deps, env, storage_key, variable)
+                        .map(|variable| (key, variable)),
+                ),
+                Ok(None) if allow_missing => None,
+                Ok(None) => Some(Err(StdError::generic_err(format!(
+                    "Variable not found - key: {key}"
+                )))),
+                Err(err) => Some(Err(err)),
+            }
         })
         .collect::<StdResult<BTreeMap<String, Variable>>>()
 }
 
 pub fn qy_get_all_variables(
     deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<Vec<(String, Variable)>> {
+    let namespace = namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+
+    VARIABLES
+        .prefix(namespace.clone())
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter_map(live_variables(deps, env, namespace))
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_get_variable_with_meta(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<VariableWithMeta> {
+    let value = qy_get_variable(deps, env, namespace.clone(), key.clone())?;
+
+    Ok(VariableWithMeta {
+        value,
+        metadata: METADATA.may_load(deps.storage, variable_key(namespace, &key))?,
+    })
+}
+
+pub fn qy_get_all_variables_with_meta(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, VariableWithMeta)>> {
+    qy_get_all_variables(deps, env, namespace.clone(), start_after, limit)?
+        .into_iter()
+        .map(|(key, value)| {
+            let metadata =
+                METADATA.may_load(deps.storage, variable_key(namespace.clone(), &key))?;
+            Ok((key, VariableWithMeta { value, metadata }))
+        })
+        .collect()
+}
+
+pub fn qy_variables_by_tag(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    tag: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, Variable)>> {
+    let namespace = namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+
+    TAGS.prefix((namespace.clone(), tag))
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter_map(|key| {
+            let key = match key {
+                Ok(key) => key,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if PRIVATE_KEYS.has(deps.storage, (namespace.clone(), key.clone())) {
+                return None;
+            }
+
+            may_load_variable(deps.storage, &env.block, (namespace.clone(), key.clone()))
+                .map(|variable| variable.map(|variable| (key, variable)))
+                .transpose()
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_frozen_variables(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, Expiration)>> {
+    FROZEN
+        .prefix(namespace.unwrap_or(DEFAULT_NAMESPACE.to_string()))
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, until)| !until.is_expired(&env.block))
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_variables_by_prefix(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    prefix: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<StorageOrder>,
+) -> StdResult<Vec<(String, Variable)>> {
+    range_variables(
+        deps,
+        env,
+        namespace,
+        Some(prefix.as_bytes().to_vec()),
+        prefix_upper_bound(&prefix),
+        start_after,
+        limit,
+        order,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn qy_variables_in_range(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    min: Option<String>,
+    max: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<StorageOrder>,
+) -> StdResult<Vec<(String, Variable)>> {
+    range_variables(
+        deps,
+        env,
+        namespace,
+        min.map(String::into_bytes),
+        max.map(String::into_bytes),
+        start_after,
+        limit,
+        order,
+    )
+}
+
+/// Iterate the variables of `namespace` with key in `[min, max)`, resuming after `start_after` in the direction of `order`
+#[allow(clippy::too_many_arguments)]
+fn range_variables(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    min: Option<Vec<u8>>,
+    max: Option<Vec<u8>>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<StorageOrder>,
+) -> StdResult<Vec<(String, Variable)>> {
+    let order: Order = order.unwrap_or(StorageOrder::Ascending).into();
+    let start_after = start_after.map(String::into_bytes);
+
+    let (min, max) = match order {
+        Order::Ascending => (
+            match (min, start_after) {
+                (Some(min), Some(start_after)) if start_after < min => {
+                    Some(Bound::InclusiveRaw(min))
+                }
+                (_, Some(start_after)) => Some(Bound::ExclusiveRaw(start_after)),
+                (min, None) => min.map(Bound::InclusiveRaw),
+            },
+            max.map(Bound::ExclusiveRaw),
+        ),
+        Order::Descending => (
+            min.map(Bound::InclusiveRaw),
+            match (max, start_after) {
+                (Some(max), Some(start_after)) if start_after > max => {
+                    Some(Bound::ExclusiveRaw(max))
+                }
+                (max, start_after) => start_after.or(max).map(Bound::ExclusiveRaw),
+            },
+        ),
+    };
+
+    let namespace = namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+
+    VARIABLES
+        .prefix(namespace.clone())
+        .range(deps.storage, min, max, order)
+        .filter_map(live_variables(deps, env, namespace))
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+/// Filter out the expired and private variables of `namespace` and replace the others with their active scheduled value
+fn live_variables<'a>(
+    deps: Deps<'a>,
+    env: &'a Env,
+    namespace: String,
+) -> impl FnMut(VariableItem) -> Option<VariableItem> + 'a {
+    move |item| {
+        let (key, variable) = match item {
+            Ok(item) => item,
+            Err(err) => return Some(Err(err)),
+        };
+        let storage_key = (namespace.clone(), key.clone());
+
+        if PRIVATE_KEYS.has(deps.storage, storage_key.clone()) {
+            return None;
+        }
+
+        match is_expired(deps.storage, &env.block, storage_key.clone()) {
+            Ok(true) => None,
+            Ok(false) => Some(
+                active_value(deps.storage, &env.block, storage_key, variable)
+                    .map(|variable| (key, variable)),
+            ),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Fail if `key` is private, since queries have no sender to authorize
+fn assert_public(deps: Deps, key: &VariableKey) -> StdResult<()> {
+    if PRIVATE_KEYS.has(deps.storage, key.clone()) {
+        return Err(StdError::generic_err(format!(
+            "variable is private - key: {}",
+            key.1
+        )));
+    }
+
+    Ok(())
+}
+
+/// Keys whose new value is carried by `msg`
+fn valued_keys(msg: &ExecuteMsg) -> Vec<VariableKey> {
+    match msg {
+        ExecuteMsg::RegisterVariable(msg)
+        | ExecuteMsg::UpdateVariable(msg)
+        | ExecuteMsg::ProposeUpdate(msg)
+        | ExecuteMsg::Upsert(msg) => vec![variable_key(msg.namespace.clone(), &msg.key)],
+        ExecuteMsg::RegisterVariables(msgs) | ExecuteMsg::UpdateVariables(msgs) => msgs
+            .iter()
+            .map(|msg| variable_key(msg.namespace.clone(), &msg.key))
+            .collect(),
+        ExecuteMsg::Batch(operations) => operations
+            .iter()
+            .filter_map(|operation| match operation {
+                Operation::Register(msg) | Operation::Update(msg) | Operation::Upsert(msg) => {
+                    Some(variable_key(msg.namespace.clone(), &msg.key))
+                }
+                Operation::Remove(_) => None,
+            })
+            .collect(),
+        ExecuteMsg::CompareAndSwap(msg) => vec![variable_key(msg.namespace.clone(), &msg.key)],
+        ExecuteMsg::ScheduleVariable(msg) => vec![variable_key(msg.namespace.clone(), &msg.key)],
+        _ => vec![],
+    }
+}
+
+/// Smallest key greater than every key starting with `prefix`. `None` if unbounded
+fn prefix_upper_bound(prefix: &str) -> Option<Vec<u8>> {
+    let mut bytes = prefix.as_bytes().to_vec();
+
+    while let Some(last) = bytes.pop() {
+        if last < u8::MAX {
+            bytes.push(last + 1);
+            return Some(bytes);
+        }
+    }
+
+    None
+}
+
+pub fn qy_variable_history(
+    deps: Deps,
+    namespace: Option<String>,
+    key: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, VariableHistoryEntry)>> {
+    let key = variable_key(namespace, &key);
+
+    assert_public(deps, &key)?;
+
+    HISTORY
+        .prefix(key)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+/// Value of `key` at the end of block `height`
+pub fn qy_variable_at_height(
+    deps: Deps,
+    namespace: Option<String>,
+    key: String,
+    height: u64,
+) -> StdResult<Option<Variable>> {
+    let key = variable_key(namespace, &key);
+
+    assert_public(deps, &key)?;
+
+    VARIABLES.may_load_at_height(deps.storage, key, height.saturating_add(1))
+}
+
+pub fn qy_roles(deps: Deps, address: String) -> StdResult<Vec<RoleGrant>> {
+    Ok(ROLES
+        .may_load(deps.storage, &address.into_addr(deps.api)?)?
+        .unwrap_or_default())
+}
+
+pub fn qy_all_roles(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(Addr, Vec<RoleGrant>)>> {
+    let start_after = start_after
+        .map(|address| address.into_addr(deps.api))
+        .transpose()?;
+
+    ROLES
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_timelock(deps: Deps, namespace: Option<String>, key: String) -> StdResult<Option<u64>> {
+    TIMELOCKS.may_load(deps.storage, variable_key(namespace, &key))
+}
+
+pub fn qy_pending_timelock(
+    deps: Deps,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<Option<PendingTimelock>> {
+    PENDING_TIMELOCKS.may_load(deps.storage, variable_key(namespace, &key))
+}
+
+pub fn qy_pending_update(
+    deps: Deps,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<Option<PendingUpdate>> {
+    let key = variable_key(namespace, &key);
+
+    assert_public(deps, &key)?;
+
+    PENDING_UPDATES.may_load(deps.storage, key)
+}
+
+pub fn qy_all_pending_updates(
+    deps: Deps,
+    namespace: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, PendingUpdate)>> {
+    let namespace = namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+
+    PENDING_UPDATES
+        .prefix(namespace.clone())
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            item.as_ref().map_or(true, |(key, _)| {
+                !PRIVATE_KEYS.has(deps.storage, (namespace.clone(), key.clone()))
+            })
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_proposal(deps: Deps, proposal_id: u64) -> StdResult<Proposal> {
+    let proposal = PROPOSALS
+        .load(deps.storage, proposal_id)
+        .map_err(|_| StdError::generic_err(format!("Proposal not found: {proposal_id}")))?;
+
+    for key in valued_keys(&proposal.msg) {
+        assert_public(deps, &key)?;
+    }
+
+    Ok(proposal)
+}
+
+pub fn qy_owner_nominations(
+    deps: Deps,
+    env: &Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(Addr, Expiration)>> {
+    let start_after = start_after
+        .map(|address| address.into_addr(deps.api))
+        .transpose()?;
+
+    NOMINATIONS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, expires_at)| !expires_at.is_expired(&env.block))
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_proposals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, Proposal)>> {
+    PROPOSALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, proposal)| {
+                valued_keys(&proposal.msg)
+                    .into_iter()
+                    .all(|key| !PRIVATE_KEYS.has(deps.storage, key))
+            })
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_schema(
+    deps: Deps,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<Option<VariableSchema>> {
+    SCHEMAS.may_load(deps.storage, variable_key(namespace, &key))
+}
+
+pub fn qy_all_schemas(
+    deps: Deps,
+    namespace: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, VariableSchema)>> {
+    SCHEMAS
+        .prefix(namespace.unwrap_or(DEFAULT_NAMESPACE.to_string()))
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .collect()
+}
+
+pub fn qy_namespace(deps: Deps, namespace: String) -> StdResult<Namespace> {
+    NAMESPACES
+        .load(deps.storage, namespace.clone())
+        .map_err(|_| StdError::generic_err(format!("Namespace not found: {namespace}")))
+}
+
+pub fn qy_namespaces(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, Namespace)>> {
     rhaki_cw_plus::storage::map::get_items(
         deps.storage,
-        &VARIABLES,
-        cosmwasm_std::Order::Ascending,
+        &NAMESPACES,
+        Order::Ascending,
         limit,
         start_after,
     )
 }
+
+pub fn qy_subscriptions(
+    deps: Deps,
+    namespace: Option<String>,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Subscription>> {
+    let namespace = namespace.unwrap_or(DEFAULT_NAMESPACE.to_string());
+    let start_after = start_after
+        .map(|(prefix, contract)| -> StdResult<(String, Addr)> {
+            Ok((prefix, contract.into_addr(deps.api)?))
+        })
+        .transpose()?;
+
+    SUBSCRIPTIONS
+        .sub_prefix(namespace.clone())
+        .range(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|(prefix, contract)| Bound::exclusive((prefix.clone(), contract))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| {
+            let ((prefix, contract), on_error) = item?;
+            Ok(Subscription {
+                namespace: namespace.clone(),
+                prefix,
+                contract,
+                on_error,
+            })
+        })
+        .collect()
+}
+
+pub fn qy_expiring_variables(
+    deps: Deps,
+    env: &Env,
+    within: Duration,
+    start_after: Option<ExpiringVariable>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ExpiringVariable>> {
+    let (index, now, until) = match within {
+        Duration::Height(blocks) => (
+            EXPIRING_AT_HEIGHT,
+            env.block.height,
+            env.block.height.saturating_add(blocks),
+        ),
+        Duration::Time(seconds) => (
+            EXPIRING_AT_TIME,
+            env.block.time.nanos(),
+            env.block
+                .time
+                .nanos()
+                .saturating_add(seconds.saturating_mul(1_000_000_000)),
+        ),
+    };
+
+    // Variables expiring at `now` are already expired
+    let first = (now.saturating_add(1), String::new(), String::new());
+
+    let min = match start_after.and_then(|start_after| {
+        expiration_index(&start_after.expires_at)
+            .map(|(_, at)| (at, start_after.namespace, start_after.key))
+    }) {
+        Some(start_after) if start_after > first => Bound::exclusive(start_after),
+        _ => Bound::inclusive(first),
+    };
+
+    let max = Bound::exclusive((until.saturating_add(1), String::new(), String::new()));
+
+    index
+        .keys(deps.storage, Some(min), Some(max), Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| {
+            let (at, namespace, key) = item?;
+            Ok(ExpiringVariable {
+                namespace,
+                key,
+                expires_at: match within {
+                    Duration::Height(_) => Expiration::AtHeight(at),
+                    Duration::Time(_) => Expiration::AtTime(Timestamp::from_nanos(at)),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Active scheduled value of `key` followed by the pending ones
+pub fn qy_schedule(
+    deps: Deps,
+    env: &Env,
+    namespace: Option<String>,
+    key: String,
+) -> StdResult<Vec<ScheduledValue>> {
+    let key = variable_key(namespace, &key);
+
+    assert_public(deps, &key)?;
+
+    let schedule = SCHEDULES.may_load(deps.storage, key)?.unwrap_or_default();
+
+    let active = schedule
+        .iter()
+        .rposition(|scheduled| scheduled.activates_at.is_triggered(&env.block))
+        .unwrap_or_default();
+
+    Ok(schedule.into_iter().skip(active).collect())
+}
@@ -1,6 +1,7 @@
-use cosmwasm_std::{Addr, Response, StdError};
+use cosmwasm_std::{Addr, Response, StdError, Timestamp};
+use cw_utils::{Expiration, Scheduled};
 use thiserror::Error;
-use variable_manager_pkg::definitions::Variable;
+use variable_manager_pkg::definitions::{Role, Variable};
 
 pub type ContractResponse = Result<Response, ContractError>;
 pub type ContractResult<T> = Result<T, ContractError>;
@@ -19,6 +20,12 @@ pub enum ContractError {
     #[error("Key not found: {key}")]
     KeyNotFound { key: String },
 
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Contract is not paused")]
+    NotPaused {},
+
     #[error("Empty update owner msg")]
     InvalidUpdateOwnerMsg,
 
@@ -27,4 +34,131 @@ pub enum ContractError {
 
     #[error("Address is not a owner: {addr}")]
     IsNotOwner { addr: Addr },
+
+    #[error("No pending nomination for {addr}")]
+    NominationNotFound { addr: Addr },
+
+    #[error("Nomination of {addr} expired: {expires_at}")]
+    NominationExpired { addr: Addr, expires_at: Expiration },
+
+    #[error("{addr} is not an owner and has no role allowing to modify {key}")]
+    MissingRole { addr: Addr, key: String },
+
+    #[error("{addr} is not an owner and has no role allowing to read the private key {key}")]
+    PrivateKey { addr: Addr, key: String },
+
+    #[error("Role {role:?} on prefix \"{prefix}\" alredy granted to {addr}")]
+    RoleAlredyGranted {
+        addr: Addr,
+        role: Role,
+        prefix: String,
+    },
+
+    #[error("Namespace not found: {namespace}")]
+    NamespaceNotFound { namespace: String },
+
+    #[error("Namespace alredy exists: {namespace}")]
+    NamespaceAlredyExists { namespace: String },
+
+    #[error("Namespace {namespace} still contains variables")]
+    NamespaceNotEmpty { namespace: String },
+
+    #[error("{contract} is not subscribed to {prefix}")]
+    SubscriptionNotFound { contract: Addr, prefix: String },
+
+    #[error("Invalid reply id: {id}")]
+    InvalidReplyId { id: u64 },
+
+    #[error("{0}")]
+    Semver(#[from] semver::Error),
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidContractName { contract: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    InvalidMigrationVersion { from: String, to: String },
+
+    #[error("Contract version not found, the storage must be migrated from the 1.0.4 layout")]
+    StorageMigrationRequired,
+
+    #[error("Storage already uses the current layout")]
+    StorageAlreadyMigrated,
+
+    #[error("Expiration of key {key} already reached: {expires_at}")]
+    ExpirationReached { key: String, expires_at: Expiration },
+
+    #[error("Invalid schedule for key {key}: {reason}")]
+    InvalidSchedule { key: String, reason: String },
+
+    #[error("No pending value of key {key} at {activates_at}")]
+    ScheduledValueNotFound {
+        key: String,
+        activates_at: Scheduled,
+    },
+
+    #[error("Key {key} is frozen, {until}")]
+    KeyFrozen { key: String, until: Expiration },
+
+    #[error("Key {key} expires {expires_at}, remove the expiration before freezing it")]
+    KeyExpiring { key: String, expires_at: Expiration },
+
+    #[error("Key {key} is not frozen")]
+    KeyNotFrozen { key: String },
+
+    #[error("Invalid metadata for key {key}: {reason}")]
+    InvalidMetadata { key: String, reason: String },
+
+    #[error("Compare and swap failed on key {key}: expected {expected}, found {current}")]
+    CompareAndSwapMismatch {
+        key: String,
+        expected: String,
+        current: String,
+    },
+
+    #[error("Schema violation on key {key}: {reason}")]
+    SchemaViolation { key: String, reason: String },
+
+    #[error("Key {key} is timelocked, use ProposeUpdate")]
+    KeyTimelocked { key: String },
+
+    #[error("Timelock of key {key} too long: {delay} seconds, max {max}")]
+    TimelockTooLong { key: String, delay: u64, max: u64 },
+
+    #[error("Timelock change for key {key} not executable before {eta}")]
+    TimelockChangeNotReady { key: String, eta: Timestamp },
+
+    #[error("Pending update alredy exists for key: {key}")]
+    PendingUpdateAlredyExists { key: String },
+
+    #[error("Pending update not found for key: {key}")]
+    PendingUpdateNotFound { key: String },
+
+    #[error("Pending update for key {key} not executable before {eta}")]
+    PendingUpdateNotReady { key: String, eta: Timestamp },
+
+    #[error("Proposal not found: {proposal_id}")]
+    ProposalNotFound { proposal_id: u64 },
+
+    #[error("Proposal {proposal_id} is expired")]
+    ProposalExpired { proposal_id: u64 },
+
+    #[error("Proposal {proposal_id} is not expired")]
+    ProposalNotExpired { proposal_id: u64 },
+
+    #[error("Address {addr} alredy voted proposal {proposal_id}")]
+    AlredyVoted { addr: Addr, proposal_id: u64 },
+
+    #[error("Proposal {proposal_id} has {approvals} approvals, needed {threshold}")]
+    ThresholdNotReached {
+        proposal_id: u64,
+        approvals: u64,
+        threshold: u64,
+    },
+
+    #[error("Role {role:?} on prefix \"{prefix}\" not granted to {addr}")]
+    RoleNotGranted {
+        addr: Addr,
+        role: Role,
+        prefix: String,
+    },
 }
@@ -1,6 +1,124 @@
-use cw_storage_plus::{Item, Map};
-use variable_manager_pkg::definitions::{Config, Variable};
+use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage};
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
+use cw_utils::Expiration;
+use variable_manager_pkg::definitions::{
+    Config, HookFailurePolicy, Namespace, PendingTimelock, PendingUpdate, Proposal, RoleGrant,
+    ScheduledValue, Variable, VariableHistoryEntry, VariableMetadata, VariableSchema,
+    DEFAULT_NAMESPACE,
+};
+
+/// `(namespace, key)` storage key of a variable
+pub type VariableKey = (String, String);
 
 pub const CONFIG: Item<Config> = Item::new("config_key");
 
-pub const VARIABLES: Map<String, Variable> = Map::new("variables_key");
+pub const VARIABLES: SnapshotMap<VariableKey, Variable> = SnapshotMap::new(
+    "variables_key",
+    "variables_key__checkpoints",
+    "variables_key__changelog",
+    Strategy::EveryBlock,
+);
+
+pub const HISTORY: Map<(String, String, u64), VariableHistoryEntry> = Map::new("history_key");
+
+pub const HISTORY_COUNTER: Item<u64> = Item::new("history_counter_key");
+
+pub const ROLES: Map<&Addr, Vec<RoleGrant>> = Map::new("roles_key");
+
+pub const TIMELOCKS: Map<VariableKey, u64> = Map::new("timelocks_key");
+
+pub const PENDING_UPDATES: Map<VariableKey, PendingUpdate> = Map::new("pending_updates_key");
+
+/// Lowering or removal of a timelock, applicable once the current delay is elapsed
+pub const PENDING_TIMELOCKS: Map<VariableKey, PendingTimelock> = Map::new("pending_timelocks_key");
+
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals_key");
+
+pub const PROPOSAL_COUNTER: Item<u64> = Item::new("proposal_counter_key");
+
+pub const SCHEMAS: Map<VariableKey, VariableSchema> = Map::new("schemas_key");
+
+pub const NAMESPACES: Map<String, Namespace> = Map::new("namespaces_key");
+
+pub fn variable_key(namespace: Option<String>, key: &str) -> VariableKey {
+    (
+        namespace.unwrap_or(DEFAULT_NAMESPACE.to_string()),
+        key.to_string(),
+    )
+}
+
+pub const SUBSCRIPTIONS: Map<(String, String, &Addr), HookFailurePolicy> =
+    Map::new("subscriptions_key");
+
+pub const EXPIRATIONS: Map<VariableKey, Expiration> = Map::new("expirations_key");
+
+/// `(height or nanos, namespace, key)` of the variables expiring at a height or a time
+pub type ExpirationIndex = Map<'static, (u64, String, String), ()>;
+
+pub const EXPIRING_AT_HEIGHT: ExpirationIndex = Map::new("expiring_at_height_key");
+
+pub const EXPIRING_AT_TIME: ExpirationIndex = Map::new("expiring_at_time_key");
+
+/// Index of `expiration` and its value. `None` for `Expiration::Never`
+pub fn expiration_index(expiration: &Expiration) -> Option<(ExpirationIndex, u64)> {
+    match expiration {
+        Expiration::AtHeight(height) => Some((EXPIRING_AT_HEIGHT, *height)),
+        Expiration::AtTime(time) => Some((EXPIRING_AT_TIME, time.nanos())),
+        Expiration::Never {} => None,
+    }
+}
+
+pub fn is_expired(storage: &dyn Storage, block: &BlockInfo, key: VariableKey) -> StdResult<bool> {
+    Ok(EXPIRATIONS
+        .may_load(storage, key)?
+        .is_some_and(|expiration| expiration.is_expired(block)))
+}
+
+/// Load the variable at `key`, replaced by its active scheduled value if any. `None` if not registered or expired
+pub fn may_load_variable(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    key: VariableKey,
+) -> StdResult<Option<Variable>> {
+    if is_expired(storage, block, key.clone())? {
+        return Ok(None);
+    }
+
+    VARIABLES
+        .may_load(storage, key.clone())?
+        .map(|variable| active_value(storage, block, key, variable))
+        .transpose()
+}
+
+/// Values scheduled for a key, sorted by activation. Activated values are dropped when the key is written
+pub const SCHEDULES: Map<VariableKey, Vec<ScheduledValue>> = Map::new("schedules_key");
+
+/// Last scheduled value of `key` activated at `block`, `stored` if none
+pub fn active_value(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    key: VariableKey,
+    stored: Variable,
+) -> StdResult<Variable> {
+    Ok(SCHEDULES
+        .may_load(storage, key)?
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .find(|scheduled| scheduled.activates_at.is_triggered(block))
+        .map_or(stored, |scheduled| scheduled.value))
+}
+
+pub const METADATA: Map<VariableKey, VariableMetadata> = Map::new("metadata_key");
+
+/// `(namespace, tag, key)` of the tagged variables
+pub const TAGS: Map<(String, String, String), ()> = Map::new("tags_key");
+
+/// Keys that can not be changed until the expiration is reached
+pub const FROZEN: Map<VariableKey, Expiration> = Map::new("frozen_key");
+
+/// Addresses nominated as owner, waiting for `AcceptOwnership`
+pub const NOMINATIONS: Map<&Addr, Expiration> = Map::new("nominations_key");
+
+/// Keys set as not public
+pub const PRIVATE_KEYS: Map<VariableKey, ()> = Map::new("private_keys_key");
@@ -0,0 +1,175 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, ExprPath, Fields, Ident, LitStr, Result, Type};
+
+/// Implement `variable_manager_pkg::definitions::FromVariableManager` for a struct with named fields.
+///
+/// Every field is loaded from the key with the same name, unless specified otherwise:
+/// - `#[vm(namespace = "...")]` on the struct: namespace queried (`DEFAULT_NAMESPACE` if omitted);
+/// - `#[vm(key = "...")]` on a field: key of the field;
+/// - `#[vm(default)]` on a field: use `Default::default()` if the key is not registered;
+/// - `#[vm(default = "path::to::fn")]` on a field: call `fn()` if the key is not registered.
+#[proc_macro_derive(FromVariableManager, attributes(vm))]
+pub fn derive_from_variable_manager(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+enum FieldDefault {
+    None,
+    Trait,
+    Function(ExprPath),
+}
+
+struct VmField {
+    ident: Ident,
+    ty: Type,
+    key: String,
+    default: FieldDefault,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let namespace = parse_namespace(&input)?;
+    let fields = parse_fields(&input)?;
+
+    let namespace = match namespace {
+        Some(namespace) => quote!(Some(#namespace.to_string())),
+        None => quote!(None),
+    };
+
+    let keys = fields.iter().map(|field| &field.key);
+
+    let values = fields.iter().map(|field| {
+        let VmField {
+            ident,
+            ty,
+            key,
+            default,
+        } = field;
+        let field_name = ident.to_string();
+
+        let missing = match default {
+            FieldDefault::None => quote! {
+                return Err(::variable_manager_pkg::definitions::VariableError::Field {
+                    field: #field_name,
+                    key: #key.to_string(),
+                    source: Box::new(::variable_manager_pkg::definitions::VariableError::NotFound {
+                        key: #key.to_string(),
+                    }),
+                })
+            },
+            FieldDefault::Trait => quote!(::core::default::Default::default()),
+            FieldDefault::Function(path) => quote!(#path()),
+        };
+
+        quote! {
+            #ident: match variables.remove(#key) {
+                Some(variable) => <#ty as ::variable_manager_pkg::definitions::FromVariable>::from_variable(variable)
+                    .map_err(|err| ::variable_manager_pkg::definitions::VariableError::Field {
+                        field: #field_name,
+                        key: #key.to_string(),
+                        source: Box::new(err),
+                    })?,
+                None => #missing,
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::variable_manager_pkg::definitions::FromVariableManager for #name #ty_generics #where_clause {
+            fn namespace() -> Option<String> {
+                #namespace
+            }
+
+            fn keys() -> Vec<String> {
+                vec![#(#keys.to_string()),*]
+            }
+
+            fn from_variables(
+                mut variables: ::std::collections::BTreeMap<String, ::variable_manager_pkg::definitions::Variable>,
+            ) -> Result<Self, ::variable_manager_pkg::definitions::VariableError> {
+                Ok(Self {
+                    #(#values),*
+                })
+            }
+        }
+    })
+}
+
+fn parse_namespace(input: &DeriveInput) -> Result<Option<String>> {
+    let mut namespace = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("vm")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("namespace") {
+                namespace = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported vm attribute, expected `namespace`"))
+            }
+        })?;
+    }
+
+    Ok(namespace)
+}
+
+fn parse_fields(input: &DeriveInput) -> Result<Vec<VmField>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "FromVariableManager requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "FromVariableManager can only be derived for structs",
+            ))
+        }
+    };
+
+    fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().unwrap();
+            let mut key = ident.to_string();
+            let mut default = FieldDefault::None;
+
+            for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("vm")) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("key") {
+                        key = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else if meta.path.is_ident("default") {
+                        default = if meta.input.peek(syn::Token![=]) {
+                            FieldDefault::Function(meta.value()?.parse::<LitStr>()?.parse()?)
+                        } else {
+                            FieldDefault::Trait
+                        };
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported vm attribute, expected `key` or `default`"))
+                    }
+                })?;
+            }
+
+            Ok(VmField {
+                ident,
+                ty: field.ty.clone(),
+                key,
+                default,
+            })
+        })
+        .collect()
+}
@@ -3,14 +3,26 @@ pub mod msgs {
 
     use cosmwasm_schema::{cw_serde, QueryResponses};
     use cosmwasm_std::Addr;
+    use cw_utils::{Duration, Expiration, Scheduled};
+    use rhaki_cw_plus::storage::StorageOrder;
 
-    use crate::definitions::Config;
+    use crate::definitions::{
+        Config, ExpiringVariable, HookFailurePolicy, Namespace, PendingTimelock, PendingUpdate,
+        Proposal, Role, RoleGrant, ScheduledValue, Subscription, VariableHistoryEntry,
+        VariableSchema, VariableWithMeta,
+    };
 
     use super::definitions::Variable;
 
     #[cw_serde]
     pub struct InstantiateMsg {
         pub owners: Vec<String>,
+        /// Number of owner approvals required to apply an owner action. Default to 1
+        pub threshold: Option<u64>,
+        /// Address allowed to `Pause` and `Unpause` the contract
+        pub guardian: Option<String>,
+        /// Max number of references followed by `GetVariable`. Default to `DEFAULT_MAX_REFERENCE_DEPTH`
+        pub max_reference_depth: Option<u32>,
     }
 
     #[cw_serde]
@@ -20,63 +32,491 @@ pub mod msgs {
         UpdateVariable(RegisterVariableMsg),
         UpdateVariables(Vec<RegisterVariableMsg>),
         RemoveVariable(RemoveVariableMsg),
+        RemoveVariables(Vec<RemoveVariableMsg>),
+        RemoveByPrefix(RemoveByPrefixMsg),
         UpdateOwners(UpdateOwnerMsg),
+        GrantRole(RoleMsg),
+        RevokeRole(RoleMsg),
+        SetTimelock(SetTimelockMsg),
+        ProposeUpdate(RegisterVariableMsg),
+        ExecutePending {
+            namespace: Option<String>,
+            key: String,
+        },
+        CancelPending {
+            namespace: Option<String>,
+            key: String,
+        },
+        Vote {
+            proposal_id: u64,
+        },
+        ExecuteProposal {
+            proposal_id: u64,
+        },
+        CloseProposal {
+            proposal_id: u64,
+        },
+        SetSchema(SetSchemaMsg),
+        CreateNamespace(CreateNamespaceMsg),
+        /// Delete an empty namespace, with the roles, schemas, subscriptions, timelocks and visibility flags bound to it
+        DeleteNamespace {
+            namespace: String,
+        },
+        UpdateNamespaceOwners(UpdateNamespaceOwnersMsg),
+        Subscribe(SubscribeMsg),
+        Unsubscribe(UnsubscribeMsg),
+        /// Apply all the operations in order. If one of them fails, the whole batch is reverted
+        Batch(Vec<Operation>),
+        /// Register the variable if the key is not registered, update it otherwise
+        Upsert(RegisterVariableMsg),
+        CompareAndSwap(CompareAndSwapMsg),
+        /// Remove up to `limit` expired variables. Callable by anyone
+        PruneExpired {
+            limit: Option<u32>,
+        },
+        ScheduleVariable(ScheduleVariableMsg),
+        SetMetadata(SetMetadataMsg),
+        /// Cancel the value scheduled at `activates_at`, if not activated yet
+        CancelScheduled {
+            namespace: Option<String>,
+            key: String,
+            activates_at: Scheduled,
+        },
+        /// Reject any change to `key` until `until` is reached. `None` (or `Expiration::Never`) freezes the key forever,
+        /// and a key frozen forever can not be unfrozen nor frozen again
+        FreezeVariable {
+            namespace: Option<String>,
+            key: String,
+            until: Option<Expiration>,
+        },
+        /// Unfreeze a key frozen until an expiration
+        UnfreezeVariable {
+            namespace: Option<String>,
+            key: String,
+        },
+        /// Reject every message except `Unpause`, `SetGuardian` and the proposal votes. Callable only by the guardian
+        Pause {},
+        /// Callable only by the guardian
+        Unpause {},
+        /// Set the guardian. `None` remove the guardian
+        SetGuardian {
+            guardian: Option<String>,
+        },
+        SetMaxReferenceDepth {
+            max_reference_depth: u32,
+        },
+        /// Nominate `address` as owner. The address is added to the owners once it sends `AcceptOwnership`.
+        /// `None` (or `Expiration::Never`) never expires the nomination
+        NominateOwner {
+            address: String,
+            expires_at: Option<Expiration>,
+        },
+        RevokeNomination {
+            address: String,
+        },
+        /// Accept a pending nomination of the sender
+        AcceptOwnership {},
+        SetVisibility(SetVisibilityMsg),
+        /// Return the values of `keys` in the response data, encoded as `BTreeMap<String, Variable>`, with the references resolved.
+        /// Private keys can be read only by the owners and the addresses with a role covering the key
+        ReadVariables {
+            namespace: Option<String>,
+            keys: Vec<String>,
+        },
     }
 
+    /// `namespace` default to `DEFAULT_NAMESPACE` if `None`.
+    ///
+    /// Once `expires_at` is reached the variable is treated as not registered. On update, `None` keeps the current expiration
+    /// and `Expiration::Never` removes it. Ignored by `ProposeUpdate`
     #[cw_serde]
     pub struct RegisterVariableMsg {
+        pub namespace: Option<String>,
         pub key: String,
         pub value: Variable,
+        pub expires_at: Option<Expiration>,
     }
 
     impl RegisterVariableMsg {
         pub fn new(key: String, value: Variable) -> Self {
-            Self { key, value }
+            Self {
+                namespace: None,
+                key,
+                value,
+                expires_at: None,
+            }
         }
     }
 
+    /// Set `key` to `new` only if its current value is `expected` (`None` if the key must not be registered)
+    #[cw_serde]
+    pub struct CompareAndSwapMsg {
+        pub namespace: Option<String>,
+        pub key: String,
+        pub expected: Option<Variable>,
+        pub new: Variable,
+    }
+
+    /// Replace the value of a registered `key` with `value` once `activates_at` is reached.
+    ///
+    /// All the values scheduled for a key must be scheduled at a height or all at a time
+    #[cw_serde]
+    pub struct ScheduleVariableMsg {
+        pub namespace: Option<String>,
+        pub key: String,
+        pub value: Variable,
+        pub activates_at: Scheduled,
+    }
+
+    #[cw_serde]
+    pub enum Operation {
+        Register(RegisterVariableMsg),
+        Update(RegisterVariableMsg),
+        /// Register the variable if the key is not registered, update it otherwise
+        Upsert(RegisterVariableMsg),
+        Remove(RemoveVariableMsg),
+    }
+
     #[cw_serde]
     pub struct RemoveVariableMsg {
+        pub namespace: Option<String>,
         pub key: String,
     }
 
+    /// Scan up to `limit` keys starting with `prefix` and remove them. Repeat the message until `has_more` is `false`.
+    ///
+    /// Timelocked and frozen keys are skipped and reported in the `skipped` attributes. Pass the `last_key` attribute
+    /// as `start_after` to continue after the skipped keys
+    #[cw_serde]
+    pub struct RemoveByPrefixMsg {
+        pub namespace: Option<String>,
+        pub prefix: String,
+        pub start_after: Option<String>,
+        pub limit: Option<u32>,
+    }
+
+    /// Addresses in `add` are nominated without expiration and become owners only after sending `AcceptOwnership`
     #[cw_serde]
     pub struct UpdateOwnerMsg {
         pub add: Option<Vec<String>>,
         pub remove: Option<Vec<String>>,
+        pub threshold: Option<u64>,
+    }
+
+    #[cw_serde]
+    pub struct RoleMsg {
+        pub address: String,
+        pub role: Role,
+        pub namespace: Option<String>,
+        pub prefix: String,
+    }
+
+    /// Set the delay (in seconds) between `ProposeUpdate` and `ExecutePending` for `key`, up to `MAX_TIMELOCK_DELAY`.
+    /// `None` remove the timelock.
+    ///
+    /// Raising the delay applies immediately. Lowering or removing it is scheduled after the current delay,
+    /// and applied by sending the same `SetTimelockMsg` again once the eta is reached
+    #[cw_serde]
+    pub struct SetTimelockMsg {
+        pub namespace: Option<String>,
+        pub key: String,
+        pub delay: Option<u64>,
+    }
+
+    /// Set the description and the tags of `key`. `None` fields are left unchanged
+    #[cw_serde]
+    pub struct SetMetadataMsg {
+        pub namespace: Option<String>,
+        pub key: String,
+        pub description: Option<String>,
+        pub tags: Option<Vec<String>>,
+    }
+
+    /// Private keys are hidden from the queries and can be read only through `ReadVariables`.
+    /// Keys are public by default, and the flag is kept when the key is removed
+    #[cw_serde]
+    pub struct SetVisibilityMsg {
+        pub namespace: Option<String>,
+        pub key: String,
+        pub public: bool,
+    }
+
+    /// Set the schema enforced on `key`. `None` remove the schema
+    #[cw_serde]
+    pub struct SetSchemaMsg {
+        pub namespace: Option<String>,
+        pub key: String,
+        pub schema: Option<VariableSchema>,
+    }
+
+    #[cw_serde]
+    pub struct CreateNamespaceMsg {
+        pub namespace: String,
+        pub owners: Vec<String>,
+    }
+
+    #[cw_serde]
+    pub struct UpdateNamespaceOwnersMsg {
+        pub namespace: String,
+        pub add: Option<Vec<String>>,
+        pub remove: Option<Vec<String>>,
+    }
+
+    /// Notify `contract` with a `HookMsg` every time a key starting with `prefix` is updated or removed
+    #[cw_serde]
+    pub struct SubscribeMsg {
+        pub namespace: Option<String>,
+        pub prefix: String,
+        pub contract: String,
+        pub on_error: HookFailurePolicy,
+    }
+
+    #[cw_serde]
+    pub struct UnsubscribeMsg {
+        pub namespace: Option<String>,
+        pub prefix: String,
+        pub contract: String,
+    }
+
+    /// Message sent to the subscribers. Consumer contracts have to handle the `VariableChangedHook` variant in their `ExecuteMsg`
+    #[cw_serde]
+    pub enum HookMsg {
+        VariableChangedHook(VariableChangedHook),
+    }
+
+    /// `old` is `None` if the variable did not exist, `new` is `None` if the variable has been removed
+    #[cw_serde]
+    pub struct VariableChangedHook {
+        pub namespace: String,
+        pub key: String,
+        pub old: Option<Variable>,
+        pub new: Option<Variable>,
     }
 
     #[cw_serde]
     #[derive(QueryResponses)]
     pub enum QueryMsg {
+        /// `Variable::Reference` are resolved, up to `Config::max_reference_depth` references
         #[returns(Addr)]
-        GetVariable { key: String },
+        GetVariable {
+            namespace: Option<String>,
+            key: String,
+        },
+        /// Stored value of `key`, without resolving `Variable::Reference`
+        #[returns(Variable)]
+        GetVariableRaw {
+            namespace: Option<String>,
+            key: String,
+        },
         #[returns(BTreeMap<String, Variable>)]
-        GetVariables { keys: Vec<String> },
+        /// Fail if a key is not registered, unless `allow_missing` is `true` (missing keys are then omitted)
+        GetVariables {
+            namespace: Option<String>,
+            keys: Vec<String>,
+            allow_missing: Option<bool>,
+        },
         #[returns(Vec<(String, Variable)>)]
         AllVariables {
+            namespace: Option<String>,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        #[returns(VariableWithMeta)]
+        GetVariableWithMeta {
+            namespace: Option<String>,
+            key: String,
+        },
+        #[returns(Vec<(String, VariableWithMeta)>)]
+        AllVariablesWithMeta {
+            namespace: Option<String>,
             start_after: Option<String>,
             limit: Option<u32>,
         },
+        #[returns(Vec<(String, Variable)>)]
+        VariablesByTag {
+            namespace: Option<String>,
+            tag: String,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        /// Frozen keys of `namespace` with the expiration of the freeze
+        #[returns(Vec<(String, Expiration)>)]
+        FrozenVariables {
+            namespace: Option<String>,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        /// Variables with key starting with `prefix`. `order` default to `Ascending`
+        #[returns(Vec<(String, Variable)>)]
+        VariablesByPrefix {
+            namespace: Option<String>,
+            prefix: String,
+            start_after: Option<String>,
+            limit: Option<u32>,
+            order: Option<StorageOrder>,
+        },
+        /// Variables with key in `[min, max)`. `order` default to `Ascending`
+        #[returns(Vec<(String, Variable)>)]
+        VariablesInRange {
+            namespace: Option<String>,
+            min: Option<String>,
+            max: Option<String>,
+            start_after: Option<String>,
+            limit: Option<u32>,
+            order: Option<StorageOrder>,
+        },
         #[returns(Config)]
         Config {},
+        /// Writes of `key`. Scheduled values activated without a write and expirations have no entry
+        #[returns(Vec<(u64, VariableHistoryEntry)>)]
+        VariableHistory {
+            namespace: Option<String>,
+            key: String,
+            start_after: Option<u64>,
+            limit: Option<u32>,
+        },
+        /// Value written to `key` as of the end of `height`.
+        ///
+        /// Scheduled values and expirations are not applied, since they change the value without a write
+        #[returns(Option<Variable>)]
+        VariableAtHeight {
+            namespace: Option<String>,
+            key: String,
+            height: u64,
+        },
+        #[returns(Vec<RoleGrant>)]
+        Roles { address: String },
+        #[returns(Vec<(Addr, Vec<RoleGrant>)>)]
+        AllRoles {
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        /// Pending owner nominations, with their expiration
+        #[returns(Vec<(Addr, Expiration)>)]
+        OwnerNominations {
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        #[returns(Option<u64>)]
+        Timelock {
+            namespace: Option<String>,
+            key: String,
+        },
+        /// Scheduled lowering or removal of the timelock of `key`
+        #[returns(Option<PendingTimelock>)]
+        PendingTimelock {
+            namespace: Option<String>,
+            key: String,
+        },
+        /// Fails if `key` is private
+        #[returns(Option<PendingUpdate>)]
+        PendingUpdate {
+            namespace: Option<String>,
+            key: String,
+        },
+        /// Private keys are skipped
+        #[returns(Vec<(String, PendingUpdate)>)]
+        AllPendingUpdates {
+            namespace: Option<String>,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        /// Fails if the proposal carries a value of a private key
+        #[returns(Proposal)]
+        Proposal { proposal_id: u64 },
+        /// Proposals carrying a value of a private key are skipped
+        #[returns(Vec<(u64, Proposal)>)]
+        Proposals {
+            start_after: Option<u64>,
+            limit: Option<u32>,
+        },
+        #[returns(Option<VariableSchema>)]
+        Schema {
+            namespace: Option<String>,
+            key: String,
+        },
+        #[returns(Vec<(String, VariableSchema)>)]
+        AllSchemas {
+            namespace: Option<String>,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        #[returns(Namespace)]
+        Namespace { namespace: String },
+        #[returns(Vec<(String, Namespace)>)]
+        Namespaces {
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+        /// `start_after` is `(prefix, contract)`
+        #[returns(Vec<Subscription>)]
+        Subscriptions {
+            namespace: Option<String>,
+            start_after: Option<(String, String)>,
+            limit: Option<u32>,
+        },
+        /// Variables of every namespace expiring in the next `within`, sorted by expiration.
+        /// `within` height and time lists the variables expiring at a height and at a time respectively
+        #[returns(Vec<ExpiringVariable>)]
+        ExpiringVariables {
+            within: Duration,
+            start_after: Option<ExpiringVariable>,
+            limit: Option<u32>,
+        },
+        /// Values scheduled for `key` sorted by activation, including the active one
+        #[returns(Vec<ScheduledValue>)]
+        Schedule {
+            namespace: Option<String>,
+            key: String,
+        },
     }
 
     #[cw_serde]
-    pub struct MigrateMsg {}
+    #[derive(Default)]
+    pub struct MigrateMsg {
+        /// Convert `CONFIG` and `VARIABLES` from the 1.0.4 layout. Required when migrating from 1.0.4
+        pub migrate_storage: Option<bool>,
+        pub update_owners: Option<UpdateOwnerMsg>,
+    }
 }
 
 pub mod definitions {
 
-    use std::fmt::Display;
+    use std::{collections::BTreeMap, fmt::Display};
 
     use cosmwasm_schema::cw_serde;
-    use cosmwasm_std::{from_json, Addr, Binary, Decimal, Deps, StdError, StdResult, Uint128};
+    use cosmwasm_std::{
+        from_json, Addr, Binary, Coin, Decimal, Decimal256, Deps, Int128, SignedDecimal, StdError,
+        StdResult, Timestamp, Uint128, Uint256,
+    };
+    use cw_utils::{Duration, Expiration, Scheduled};
     use serde::de::DeserializeOwned;
+    use thiserror::Error;
+    pub use variable_manager_derive::FromVariableManager;
+
+    use crate::msgs::ExecuteMsg;
+
+    /// Namespace used when no namespace is specified. It always exists and it's owned by `Config::owners`
+    pub const DEFAULT_NAMESPACE: &str = "default";
+
+    pub const DEFAULT_MAX_REFERENCE_DEPTH: u32 = 5;
+
+    /// Max delay (in seconds) of a timelock
+    pub const MAX_TIMELOCK_DELAY: u64 = 365 * 24 * 60 * 60;
 
     #[cw_serde]
     pub struct Config {
         pub owners: Vec<Addr>,
+        pub threshold: u64,
+        pub guardian: Option<Addr>,
+        #[serde(default)]
+        pub paused: bool,
+        #[serde(default = "default_max_reference_depth")]
+        pub max_reference_depth: u32,
+    }
+
+    fn default_max_reference_depth() -> u32 {
+        DEFAULT_MAX_REFERENCE_DEPTH
     }
 
     impl Config {
@@ -95,6 +535,27 @@ pub mod definitions {
                 return Err(StdError::generic_err("Invalid 0 owners. Needed at least 1"));
             }
 
+            if self.threshold == 0 {
+                return Err(StdError::generic_err(
+                    "Invalid 0 threshold. Needed at least 1",
+                ));
+            }
+
+            if self.threshold > self.owners.len() as u64 {
+                return Err(StdError::generic_err(format!(
+                    "Invalid threshold {}. Greater than the number of owners {}",
+                    self.threshold,
+                    self.owners.len()
+                )));
+            }
+
+            if let Some(guardian) = self.guardian.as_ref().filter(|g| self.owners.contains(g)) {
+                return Err(StdError::generic_err(format!(
+                    "Invalid guardian {}. The guardian can't be an owner",
+                    guardian
+                )));
+            }
+
             Ok(())
         }
     }
@@ -107,6 +568,45 @@ pub mod definitions {
         U64(u64),
         Decimal(Decimal),
         Binary(Binary),
+        Bool(bool),
+        Int128(Int128),
+        I64(i64),
+        SignedDecimal(SignedDecimal),
+        Uint256(Uint256),
+        Decimal256(Decimal256),
+        Coin(Coin),
+        Coins(Vec<Coin>),
+        Timestamp(Timestamp),
+        Duration(Duration),
+        /// All the items must be of the same kind
+        List(Vec<Variable>),
+        /// All the values must be of the same kind
+        Map(BTreeMap<String, Variable>),
+        /// Resolved by `GetVariable`. Not allowed inside `List` and `Map`
+        Reference(VariableReference),
+    }
+
+    /// Key of this manager or, if `manager` is set, of another manager.
+    /// `namespace` default to `DEFAULT_NAMESPACE` if `None`
+    #[cw_serde]
+    pub struct VariableReference {
+        pub manager: Option<Addr>,
+        pub namespace: Option<String>,
+        pub key: String,
+    }
+
+    impl Display for VariableReference {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            if let Some(manager) = &self.manager {
+                write!(f, "{manager}/")?;
+            }
+            write!(
+                f,
+                "{}/{}",
+                self.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE),
+                self.key
+            )
+        }
     }
 
     impl Variable {
@@ -176,59 +676,1045 @@ pub mod definitions {
             }
         }
 
-        pub fn validate(self, deps: Deps) -> StdResult<Variable> {
-            if let Variable::Addr(val) = &self {
-                deps.api.addr_validate(val.as_ref())?;
+        pub fn unwrap_bool(&self) -> StdResult<bool> {
+            if let Variable::Bool(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Bool, {:?}",
+                    self
+                )))
+            }
+        }
+
+        pub fn unwrap_int128(&self) -> StdResult<Int128> {
+            if let Variable::Int128(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Int128, {:?}",
+                    self
+                )))
             }
+        }
 
-            Ok(self)
+        pub fn unwrap_i64(&self) -> StdResult<i64> {
+            if let Variable::I64(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not i64, {:?}",
+                    self
+                )))
+            }
         }
-    }
 
-    impl Display for Variable {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            match self {
-                Variable::String(val) => write!(f, "String: {val}"),
-                Variable::Addr(val) => write!(f, "Addr: {val}"),
-                Variable::Uint128(val) => write!(f, "Uint128: {val}"),
-                Variable::U64(val) => write!(f, "u64: {val}"),
-                Variable::Decimal(val) => write!(f, "Decimal: {val}"),
-                Variable::Binary(val) => write!(f, "Binary: {val}"),
+        pub fn unwrap_signed_decimal(&self) -> StdResult<SignedDecimal> {
+            if let Variable::SignedDecimal(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not SignedDecimal, {:?}",
+                    self
+                )))
             }
         }
-    }
-}
 
-pub mod helper {
-    use std::collections::BTreeMap;
+        pub fn unwrap_uint256(&self) -> StdResult<Uint256> {
+            if let Variable::Uint256(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Uint256, {:?}",
+                    self
+                )))
+            }
+        }
 
-    use cosmwasm_std::{QuerierWrapper, StdResult};
+        pub fn unwrap_decimal256(&self) -> StdResult<Decimal256> {
+            if let Variable::Decimal256(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Decimal256, {:?}",
+                    self
+                )))
+            }
+        }
 
-    use crate::definitions::Variable;
+        pub fn unwrap_coin(&self) -> StdResult<Coin> {
+            if let Variable::Coin(val) = self {
+                Ok(val.clone())
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Coin, {:?}",
+                    self
+                )))
+            }
+        }
 
-    use super::msgs::QueryMsg;
+        pub fn unwrap_coins(&self) -> StdResult<Vec<Coin>> {
+            if let Variable::Coins(val) = self {
+                Ok(val.clone())
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Coins, {:?}",
+                    self
+                )))
+            }
+        }
 
-    pub fn variable_manager_get_variable(
-        querier: &QuerierWrapper,
-        key: impl Into<String>,
-        address_manager_addr: impl Into<String>,
-    ) -> StdResult<Variable> {
-        querier.query_wasm_smart(
-            address_manager_addr,
-            &QueryMsg::GetVariable { key: key.into() },
-        )
-    }
+        pub fn unwrap_timestamp(&self) -> StdResult<Timestamp> {
+            if let Variable::Timestamp(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Timestamp, {:?}",
+                    self
+                )))
+            }
+        }
 
-    pub fn variable_manager_get_variables(
-        querier: &QuerierWrapper,
-        keys: Vec<impl Into<String>>,
-        address_manager_addr: impl Into<String>,
-    ) -> StdResult<BTreeMap<String, Variable>> {
-        querier.query_wasm_smart(
-            address_manager_addr,
-            &QueryMsg::GetVariables {
-                keys: keys.into_iter().map(|val| val.into()).collect(),
-            },
-        )
+        pub fn unwrap_duration(&self) -> StdResult<Duration> {
+            if let Variable::Duration(val) = self {
+                Ok(*val)
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Duration, {:?}",
+                    self
+                )))
+            }
+        }
+
+        pub fn unwrap_list(&self) -> StdResult<Vec<Variable>> {
+            if let Variable::List(val) = self {
+                Ok(val.clone())
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not List, {:?}",
+                    self
+                )))
+            }
+        }
+
+        pub fn unwrap_map(&self) -> StdResult<BTreeMap<String, Variable>> {
+            if let Variable::Map(val) = self {
+                Ok(val.clone())
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Map, {:?}",
+                    self
+                )))
+            }
+        }
+
+        pub fn unwrap_reference(&self) -> StdResult<VariableReference> {
+            if let Variable::Reference(val) = self {
+                Ok(val.clone())
+            } else {
+                Err(StdError::generic_err(format!(
+                    "Variable is not Reference, {:?}",
+                    self
+                )))
+            }
+        }
+
+        pub fn validate(self, deps: Deps) -> StdResult<Variable> {
+            match self {
+                Variable::Addr(val) => Ok(Variable::Addr(deps.api.addr_validate(val.as_ref())?)),
+                Variable::Coin(val) => {
+                    validate_denom(&val.denom)?;
+                    Ok(Variable::Coin(val))
+                }
+                Variable::Coins(val) => {
+                    for (index, coin) in val.iter().enumerate() {
+                        validate_denom(&coin.denom)?;
+
+                        if val[..index].iter().any(|prev| prev.denom == coin.denom) {
+                            return Err(StdError::generic_err(format!(
+                                "Duplicate denom {} in Coins",
+                                coin.denom
+                            )));
+                        }
+                    }
+                    Ok(Variable::Coins(val))
+                }
+                Variable::List(val) => {
+                    assert_same_kind(val.iter())?;
+                    assert_no_reference(val.iter())?;
+                    Ok(Variable::List(
+                        val.into_iter()
+                            .map(|item| item.validate(deps))
+                            .collect::<StdResult<Vec<Variable>>>()?,
+                    ))
+                }
+                Variable::Map(val) => {
+                    assert_same_kind(val.values())?;
+                    assert_no_reference(val.values())?;
+                    Ok(Variable::Map(
+                        val.into_iter()
+                            .map(|(key, item)| Ok((key, item.validate(deps)?)))
+                            .collect::<StdResult<BTreeMap<String, Variable>>>()?,
+                    ))
+                }
+                Variable::Reference(val) => Ok(Variable::Reference(VariableReference {
+                    manager: val
+                        .manager
+                        .map(|manager| deps.api.addr_validate(manager.as_ref()))
+                        .transpose()?,
+                    ..val
+                })),
+                _ => Ok(self),
+            }
+        }
+
+        pub fn kind(&self) -> &'static str {
+            match self {
+                Variable::String(_) => "String",
+                Variable::Addr(_) => "Addr",
+                Variable::Uint128(_) => "Uint128",
+                Variable::U64(_) => "U64",
+                Variable::Decimal(_) => "Decimal",
+                Variable::Binary(_) => "Binary",
+                Variable::Bool(_) => "Bool",
+                Variable::Int128(_) => "Int128",
+                Variable::I64(_) => "I64",
+                Variable::SignedDecimal(_) => "SignedDecimal",
+                Variable::Uint256(_) => "Uint256",
+                Variable::Decimal256(_) => "Decimal256",
+                Variable::Coin(_) => "Coin",
+                Variable::Coins(_) => "Coins",
+                Variable::Timestamp(_) => "Timestamp",
+                Variable::Duration(_) => "Duration",
+                Variable::List(_) => "List",
+                Variable::Map(_) => "Map",
+                Variable::Reference(_) => "Reference",
+            }
+        }
+    }
+
+    /// Denom must be 3-128 chars long, start with a letter and contain only alphanumeric chars or `/:._-`
+    fn validate_denom(denom: &str) -> StdResult<()> {
+        let valid = (3..=128).contains(&denom.len())
+            && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+            && denom
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+
+        if !valid {
+            return Err(StdError::generic_err(format!("Invalid denom: {denom}")));
+        }
+
+        Ok(())
+    }
+
+    fn assert_same_kind<'a>(mut variables: impl Iterator<Item = &'a Variable>) -> StdResult<()> {
+        if let Some(first) = variables.next() {
+            if let Some(other) = variables.find(|variable| variable.kind() != first.kind()) {
+                return Err(StdError::generic_err(format!(
+                    "Mixed kinds not allowed, found {} and {}",
+                    first.kind(),
+                    other.kind()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assert_no_reference<'a>(mut variables: impl Iterator<Item = &'a Variable>) -> StdResult<()> {
+        if variables.any(|variable| matches!(variable, Variable::Reference(_))) {
+            return Err(StdError::generic_err(
+                "Reference not allowed inside List and Map",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Type contract of a key: expected `Variable` variant and optional bounds
+    #[cw_serde]
+    pub enum VariableSchema {
+        String {
+            max_len: Option<u32>,
+            /// Allowed chars, ranges can be expressed as `a-z`. Example: `a-z0-9._`
+            charset: Option<String>,
+        },
+        Addr {},
+        Uint128 {
+            min: Option<Uint128>,
+            max: Option<Uint128>,
+        },
+        U64 {
+            min: Option<u64>,
+            max: Option<u64>,
+        },
+        Decimal {
+            min: Option<Decimal>,
+            max: Option<Decimal>,
+        },
+        Binary {
+            max_len: Option<u32>,
+        },
+        Bool {},
+        Int128 {
+            min: Option<Int128>,
+            max: Option<Int128>,
+        },
+        I64 {
+            min: Option<i64>,
+            max: Option<i64>,
+        },
+        SignedDecimal {
+            min: Option<SignedDecimal>,
+            max: Option<SignedDecimal>,
+        },
+        Uint256 {
+            min: Option<Uint256>,
+            max: Option<Uint256>,
+        },
+        Decimal256 {
+            min: Option<Decimal256>,
+            max: Option<Decimal256>,
+        },
+        Coin {
+            denom: Option<String>,
+        },
+        Coins {},
+        Timestamp {},
+        Duration {},
+        List {
+            max_len: Option<u32>,
+        },
+        Map {
+            max_len: Option<u32>,
+        },
+    }
+
+    impl VariableSchema {
+        pub fn kind(&self) -> &'static str {
+            match self {
+                VariableSchema::String { .. } => "String",
+                VariableSchema::Addr {} => "Addr",
+                VariableSchema::Uint128 { .. } => "Uint128",
+                VariableSchema::U64 { .. } => "U64",
+                VariableSchema::Decimal { .. } => "Decimal",
+                VariableSchema::Binary { .. } => "Binary",
+                VariableSchema::Bool {} => "Bool",
+                VariableSchema::Int128 { .. } => "Int128",
+                VariableSchema::I64 { .. } => "I64",
+                VariableSchema::SignedDecimal { .. } => "SignedDecimal",
+                VariableSchema::Uint256 { .. } => "Uint256",
+                VariableSchema::Decimal256 { .. } => "Decimal256",
+                VariableSchema::Coin { .. } => "Coin",
+                VariableSchema::Coins {} => "Coins",
+                VariableSchema::Timestamp {} => "Timestamp",
+                VariableSchema::Duration {} => "Duration",
+                VariableSchema::List { .. } => "List",
+                VariableSchema::Map { .. } => "Map",
+            }
+        }
+
+        pub fn validate(&self) -> StdResult<()> {
+            let invalid_bounds = match self {
+                VariableSchema::Uint128 {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::U64 {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::Decimal {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::Int128 {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::I64 {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::SignedDecimal {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::Uint256 {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                VariableSchema::Decimal256 {
+                    min: Some(min),
+                    max: Some(max),
+                } => min > max,
+                _ => false,
+            };
+
+            if invalid_bounds {
+                return Err(StdError::generic_err(
+                    "Invalid schema, min greater than max",
+                ));
+            }
+
+            Ok(())
+        }
+
+        pub fn validate_variable(&self, variable: &Variable) -> StdResult<()> {
+            match (self, variable) {
+                (VariableSchema::String { max_len, charset }, Variable::String(val)) => {
+                    assert_max_len(*max_len, val.chars().count())?;
+
+                    if let Some(charset) = charset {
+                        if let Some(char) = val.chars().find(|c| !charset_contains(charset, *c)) {
+                            return Err(StdError::generic_err(format!(
+                                "Char '{char}' not allowed by charset {charset}"
+                            )));
+                        }
+                    }
+
+                    Ok(())
+                }
+                (VariableSchema::Addr {}, Variable::Addr(_)) => Ok(()),
+                (VariableSchema::Uint128 { min, max }, Variable::Uint128(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::U64 { min, max }, Variable::U64(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::Decimal { min, max }, Variable::Decimal(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::Binary { max_len }, Variable::Binary(val)) => {
+                    assert_max_len(*max_len, val.len())
+                }
+                (VariableSchema::Bool {}, Variable::Bool(_)) => Ok(()),
+                (VariableSchema::Int128 { min, max }, Variable::Int128(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::I64 { min, max }, Variable::I64(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::SignedDecimal { min, max }, Variable::SignedDecimal(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::Uint256 { min, max }, Variable::Uint256(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::Decimal256 { min, max }, Variable::Decimal256(val)) => {
+                    assert_bounds(min, max, val)
+                }
+                (VariableSchema::Coin { denom }, Variable::Coin(val)) => match denom {
+                    Some(denom) if denom != &val.denom => Err(StdError::generic_err(format!(
+                        "Denom {} not allowed, expected {denom}",
+                        val.denom
+                    ))),
+                    _ => Ok(()),
+                },
+                (VariableSchema::Coins {}, Variable::Coins(_)) => Ok(()),
+                (VariableSchema::Timestamp {}, Variable::Timestamp(_)) => Ok(()),
+                (VariableSchema::Duration {}, Variable::Duration(_)) => Ok(()),
+                (VariableSchema::List { max_len }, Variable::List(val)) => {
+                    assert_max_len(*max_len, val.len())
+                }
+                (VariableSchema::Map { max_len }, Variable::Map(val)) => {
+                    assert_max_len(*max_len, val.len())
+                }
+                _ => Err(StdError::generic_err(format!(
+                    "Variable is not {}, {:?}",
+                    self.kind(),
+                    variable
+                ))),
+            }
+        }
+    }
+
+    fn assert_max_len(max_len: Option<u32>, len: usize) -> StdResult<()> {
+        match max_len {
+            Some(max_len) if len > max_len as usize => Err(StdError::generic_err(format!(
+                "Length {len} greater than max length {max_len}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn assert_bounds<T: PartialOrd + Display>(
+        min: &Option<T>,
+        max: &Option<T>,
+        val: &T,
+    ) -> StdResult<()> {
+        if let Some(min) = min {
+            if val < min {
+                return Err(StdError::generic_err(format!(
+                    "Value {val} lower than min {min}"
+                )));
+            }
+        }
+
+        if let Some(max) = max {
+            if val > max {
+                return Err(StdError::generic_err(format!(
+                    "Value {val} greater than max {max}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn charset_contains(charset: &str, char: char) -> bool {
+        let chars: Vec<char> = charset.chars().collect();
+        let mut i = 0;
+
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == '-' {
+                if (chars[i]..=chars[i + 2]).contains(&char) {
+                    return true;
+                }
+                i += 3;
+            } else {
+                if chars[i] == char {
+                    return true;
+                }
+                i += 1;
+            }
+        }
+
+        false
+    }
+
+    #[cw_serde]
+    pub enum Role {
+        /// Write keys and grant/revoke roles inside the scope
+        Admin,
+        /// Write keys inside the scope
+        Writer,
+        /// Read private keys inside the scope. Grants no write permission
+        Reader,
+    }
+
+    /// `role` granted over every key of `namespace` starting with `prefix` (an empty `prefix` covers all the keys)
+    #[cw_serde]
+    pub struct RoleGrant {
+        pub role: Role,
+        pub namespace: String,
+        pub prefix: String,
+    }
+
+    impl RoleGrant {
+        pub fn covers(&self, namespace: &str, key: &str) -> bool {
+            self.namespace == namespace && key.starts_with(&self.prefix)
+        }
+    }
+
+    #[cw_serde]
+    pub struct Namespace {
+        pub owners: Vec<Addr>,
+    }
+
+    impl Namespace {
+        pub fn is_owner(&self, address: &Addr) -> bool {
+            self.owners.contains(address)
+        }
+    }
+
+    /// Behaviour when a subscriber fails to handle a `HookMsg`
+    #[cw_serde]
+    pub enum HookFailurePolicy {
+        /// The error is ignored and the change is applied anyway
+        Ignore,
+        /// The whole transaction is reverted
+        Revert,
+    }
+
+    #[cw_serde]
+    pub struct Subscription {
+        pub namespace: String,
+        pub prefix: String,
+        pub contract: Addr,
+        pub on_error: HookFailurePolicy,
+    }
+
+    #[cw_serde]
+    pub struct ScheduledValue {
+        pub value: Variable,
+        pub activates_at: Scheduled,
+    }
+
+    #[cw_serde]
+    pub struct ExpiringVariable {
+        pub namespace: String,
+        pub key: String,
+        pub expires_at: Expiration,
+    }
+
+    #[cw_serde]
+    pub struct PendingTimelock {
+        pub delay: Option<u64>,
+        pub eta: Timestamp,
+    }
+
+    #[cw_serde]
+    pub struct PendingUpdate {
+        pub value: Variable,
+        pub eta: Timestamp,
+        pub proposer: Addr,
+    }
+
+    /// Owner action waiting for `Config::threshold` approvals
+    #[cw_serde]
+    pub struct Proposal {
+        pub msg: ExecuteMsg,
+        pub proposer: Addr,
+        pub approvals: Vec<Addr>,
+        pub expires: Timestamp,
+    }
+
+    impl Proposal {
+        pub fn is_expired(&self, time: Timestamp) -> bool {
+            time >= self.expires
+        }
+    }
+
+    #[cw_serde]
+    pub enum VariableAction {
+        Register,
+        Update,
+        Remove,
+    }
+
+    /// Single change applied to a key. `value` is the value stored after the change (`None` on `Remove`)
+    #[cw_serde]
+    pub struct VariableHistoryEntry {
+        pub action: VariableAction,
+        pub value: Option<Variable>,
+        pub height: u64,
+        pub time: Timestamp,
+        pub sender: Addr,
+    }
+
+    /// Block and sender of a write
+    #[cw_serde]
+    pub struct Modification {
+        pub height: u64,
+        pub time: Timestamp,
+        pub sender: Addr,
+    }
+
+    #[cw_serde]
+    pub struct VariableMetadata {
+        pub description: Option<String>,
+        pub tags: Vec<String>,
+        pub created: Modification,
+        pub updated: Modification,
+    }
+
+    /// `metadata` is `None` for variables migrated from the 1.0.4 layout and never written since
+    #[cw_serde]
+    pub struct VariableWithMeta {
+        pub value: Variable,
+        pub metadata: Option<VariableMetadata>,
+    }
+
+    #[derive(Error, Debug, PartialEq)]
+    pub enum VariableError {
+        #[error("{0}")]
+        Std(#[from] StdError),
+
+        #[error("Variable type mismatch: expected {expected}, found {actual}")]
+        TypeMismatch {
+            expected: &'static str,
+            actual: &'static str,
+        },
+
+        #[error("Variable not found: {key}")]
+        NotFound { key: String },
+
+        #[error("Field {field} (key {key}): {source}")]
+        Field {
+            field: &'static str,
+            key: String,
+            source: Box<VariableError>,
+        },
+    }
+
+    impl From<VariableError> for StdError {
+        fn from(err: VariableError) -> Self {
+            match err {
+                VariableError::Std(err) => err,
+                err => StdError::generic_err(err.to_string()),
+            }
+        }
+    }
+
+    /// Rust types that can be extracted from a `Variable`
+    pub trait FromVariable: Sized {
+        /// `Variable::kind` of the variant holding `Self`
+        const KIND: &'static str;
+
+        fn from_variable(variable: Variable) -> Result<Self, VariableError>;
+    }
+
+    macro_rules! impl_from_variable {
+        ($ty:ty, $variant:ident) => {
+            impl FromVariable for $ty {
+                const KIND: &'static str = stringify!($variant);
+
+                fn from_variable(variable: Variable) -> Result<Self, VariableError> {
+                    match variable {
+                        Variable::$variant(val) => Ok(val),
+                        other => Err(VariableError::TypeMismatch {
+                            expected: Self::KIND,
+                            actual: other.kind(),
+                        }),
+                    }
+                }
+            }
+
+            impl TryFrom<Variable> for $ty {
+                type Error = VariableError;
+
+                fn try_from(variable: Variable) -> Result<Self, Self::Error> {
+                    <$ty>::from_variable(variable)
+                }
+            }
+
+            impl From<$ty> for Variable {
+                fn from(val: $ty) -> Self {
+                    Variable::$variant(val)
+                }
+            }
+        };
+    }
+
+    impl_from_variable!(String, String);
+    impl_from_variable!(Addr, Addr);
+    impl_from_variable!(Uint128, Uint128);
+    impl_from_variable!(u64, U64);
+    impl_from_variable!(Decimal, Decimal);
+    impl_from_variable!(Binary, Binary);
+    impl_from_variable!(bool, Bool);
+    impl_from_variable!(Int128, Int128);
+    impl_from_variable!(i64, I64);
+    impl_from_variable!(SignedDecimal, SignedDecimal);
+    impl_from_variable!(Uint256, Uint256);
+    impl_from_variable!(Decimal256, Decimal256);
+    impl_from_variable!(Coin, Coin);
+    impl_from_variable!(Vec<Coin>, Coins);
+    impl_from_variable!(Timestamp, Timestamp);
+    impl_from_variable!(Duration, Duration);
+    impl_from_variable!(Vec<Variable>, List);
+    impl_from_variable!(BTreeMap<String, Variable>, Map);
+    impl_from_variable!(VariableReference, Reference);
+
+    /// Struct loaded from the variable manager with a single `GetVariables` query, usually implemented with `#[derive(FromVariableManager)]`
+    pub trait FromVariableManager: Sized {
+        /// Namespace of the keys, `None` for `DEFAULT_NAMESPACE`
+        fn namespace() -> Option<String>;
+
+        fn keys() -> Vec<String>;
+
+        fn from_variables(variables: BTreeMap<String, Variable>) -> Result<Self, VariableError>;
+    }
+
+    impl From<&str> for Variable {
+        fn from(val: &str) -> Self {
+            Variable::String(val.to_string())
+        }
+    }
+
+    impl Display for Variable {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Variable::String(val) => write!(f, "String: {val}"),
+                Variable::Addr(val) => write!(f, "Addr: {val}"),
+                Variable::Uint128(val) => write!(f, "Uint128: {val}"),
+                Variable::U64(val) => write!(f, "u64: {val}"),
+                Variable::Decimal(val) => write!(f, "Decimal: {val}"),
+                Variable::Binary(val) => write!(f, "Binary: {val}"),
+                Variable::Bool(val) => write!(f, "Bool: {val}"),
+                Variable::Int128(val) => write!(f, "Int128: {val}"),
+                Variable::I64(val) => write!(f, "i64: {val}"),
+                Variable::SignedDecimal(val) => write!(f, "SignedDecimal: {val}"),
+                Variable::Uint256(val) => write!(f, "Uint256: {val}"),
+                Variable::Decimal256(val) => write!(f, "Decimal256: {val}"),
+                Variable::Coin(val) => write!(f, "Coin: {val}"),
+                Variable::Coins(val) => write!(
+                    f,
+                    "Coins: {}",
+                    val.iter()
+                        .map(|coin| coin.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
+                Variable::Timestamp(val) => write!(f, "Timestamp: {val}"),
+                Variable::Duration(val) => write!(f, "Duration: {val}"),
+                Variable::List(val) => write!(
+                    f,
+                    "List: [{}]",
+                    val.iter()
+                        .map(|item| item.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Variable::Map(val) => write!(
+                    f,
+                    "Map: {{{}}}",
+                    val.iter()
+                        .map(|(key, item)| format!("{key}: {item}"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Variable::Reference(val) => write!(f, "Reference: {val}"),
+            }
+        }
+    }
+}
+
+pub mod factory {
+    use cosmwasm_schema::{cw_serde, QueryResponses};
+    use cosmwasm_std::Addr;
+
+    #[cw_serde]
+    pub struct InstantiateMsg {
+        pub owners: Vec<String>,
+        /// Code id of the `variable-manager` contract instantiated by `CreateManager`
+        pub manager_code_id: u64,
+    }
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        CreateManager(CreateManagerMsg),
+        SetManagerCodeId {
+            code_id: u64,
+        },
+        /// Add and remove owners of the factory. At least one owner must remain
+        UpdateOwners {
+            add: Option<Vec<String>>,
+            remove: Option<Vec<String>>,
+        },
+    }
+
+    #[cw_serde]
+    pub struct CreateManagerMsg {
+        /// Name the manager is registered under. Lowercase alphanumeric chars, `-` and `_`
+        pub protocol: String,
+        pub msg: crate::msgs::InstantiateMsg,
+        /// Wasm admin of the manager, allowed to migrate it
+        pub admin: Option<String>,
+    }
+
+    #[cw_serde]
+    #[derive(QueryResponses)]
+    pub enum QueryMsg {
+        #[returns(Config)]
+        Config {},
+        #[returns(ManagerInfo)]
+        Manager { protocol: String },
+        #[returns(Vec<ManagerInfo>)]
+        Managers {
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+    }
+
+    #[cw_serde]
+    pub struct Config {
+        pub owners: Vec<Addr>,
+        pub manager_code_id: u64,
+    }
+
+    #[cw_serde]
+    pub struct MigrateMsg {}
+
+    #[cw_serde]
+    pub struct ManagerInfo {
+        pub protocol: String,
+        pub address: Addr,
+        pub code_id: u64,
+        /// Block height of the instantiation
+        pub created_at: u64,
+    }
+}
+
+pub mod helper {
+    use std::collections::BTreeMap;
+
+    use cosmwasm_std::{Addr, QuerierWrapper, StdResult};
+    use rhaki_cw_plus::storage::StorageOrder;
+
+    use crate::{
+        definitions::{FromVariable, FromVariableManager, Variable, VariableError, VariableSchema},
+        factory::{ManagerInfo, QueryMsg as FactoryQueryMsg},
+    };
+
+    use super::msgs::QueryMsg;
+
+    /// Query `key` and convert it into `T`, failing with `VariableError::TypeMismatch` if the stored variant is not `T::KIND`
+    pub fn get_variable<T: FromVariable>(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        key: impl Into<String>,
+        address_manager_addr: impl Into<String>,
+    ) -> Result<T, VariableError> {
+        T::from_variable(variable_manager_get_variable(
+            querier,
+            namespace,
+            key,
+            address_manager_addr,
+        )?)
+    }
+
+    pub fn variable_manager_get_variable(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        key: impl Into<String>,
+        address_manager_addr: impl Into<String>,
+    ) -> StdResult<Variable> {
+        querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::GetVariable {
+                namespace: namespace.map(|val| val.to_string()),
+                key: key.into(),
+            },
+        )
+    }
+
+    pub fn variable_manager_get_variables(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        keys: Vec<impl Into<String>>,
+        address_manager_addr: impl Into<String>,
+    ) -> StdResult<BTreeMap<String, Variable>> {
+        querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::GetVariables {
+                namespace: namespace.map(|val| val.to_string()),
+                keys: keys.into_iter().map(|val| val.into()).collect(),
+                allow_missing: None,
+            },
+        )
+    }
+
+    /// Load `T` with a single `GetVariables` query
+    pub fn variable_manager_load<T: FromVariableManager>(
+        querier: &QuerierWrapper,
+        address_manager_addr: impl Into<String>,
+    ) -> Result<T, VariableError> {
+        T::from_variables(querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::GetVariables {
+                namespace: T::namespace(),
+                keys: T::keys(),
+                allow_missing: Some(true),
+            },
+        )?)
+    }
+
+    pub fn variable_manager_get_variables_by_prefix(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        prefix: impl Into<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<StorageOrder>,
+        address_manager_addr: impl Into<String>,
+    ) -> StdResult<Vec<(String, Variable)>> {
+        querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::VariablesByPrefix {
+                namespace: namespace.map(|val| val.to_string()),
+                prefix: prefix.into(),
+                start_after,
+                limit,
+                order,
+            },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn variable_manager_get_variables_in_range(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        min: Option<String>,
+        max: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<StorageOrder>,
+        address_manager_addr: impl Into<String>,
+    ) -> StdResult<Vec<(String, Variable)>> {
+        querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::VariablesInRange {
+                namespace: namespace.map(|val| val.to_string()),
+                min,
+                max,
+                start_after,
+                limit,
+                order,
+            },
+        )
+    }
+
+    /// Value written to `key` as of the end of `height`, without applying scheduled values and expirations
+    pub fn variable_manager_get_variable_at_height(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        key: impl Into<String>,
+        height: u64,
+        address_manager_addr: impl Into<String>,
+    ) -> StdResult<Option<Variable>> {
+        querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::VariableAtHeight {
+                namespace: namespace.map(|val| val.to_string()),
+                key: key.into(),
+                height,
+            },
+        )
+    }
+
+    pub fn variable_manager_get_schema(
+        querier: &QuerierWrapper,
+        namespace: Option<&str>,
+        key: impl Into<String>,
+        address_manager_addr: impl Into<String>,
+    ) -> StdResult<Option<VariableSchema>> {
+        querier.query_wasm_smart(
+            address_manager_addr,
+            &QueryMsg::Schema {
+                namespace: namespace.map(|val| val.to_string()),
+                key: key.into(),
+            },
+        )
+    }
+
+    /// Resolve `protocol` to the address of the manager registered on the factory
+    pub fn factory_get_manager(
+        querier: &QuerierWrapper,
+        protocol: impl Into<String>,
+        factory_addr: impl Into<String>,
+    ) -> StdResult<Addr> {
+        querier
+            .query_wasm_smart::<ManagerInfo>(
+                factory_addr,
+                &FactoryQueryMsg::Manager {
+                    protocol: protocol.into(),
+                },
+            )
+            .map(|manager| manager.address)
+    }
+
+    /// Query `key` on the manager registered for `protocol` and convert it into `T`
+    pub fn factory_get_variable<T: FromVariable>(
+        querier: &QuerierWrapper,
+        protocol: impl Into<String>,
+        namespace: Option<&str>,
+        key: impl Into<String>,
+        factory_addr: impl Into<String>,
+    ) -> Result<T, VariableError> {
+        let manager = factory_get_manager(querier, protocol, factory_addr)?;
+        get_variable(querier, namespace, key, manager)
     }
 }
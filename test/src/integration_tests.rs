@@ -1,18 +1,38 @@
 use {
-    cosmwasm_std::{Addr, Binary},
+    crate::{mock_consumer, mock_legacy},
+    cosmwasm_std::{
+        coin, Addr, Binary, Coin, Decimal, Decimal256, Empty, Int128, SignedDecimal, Timestamp,
+        Uint256,
+    },
+    cw_utils::{Duration, Expiration, Scheduled},
     rhaki_cw_plus::{
         math::IntoDecimal,
         multi_test::helper::{
             anyhow::Result as AnyResult,
-            build_bech32_app, create_code,
-            cw_multi_test::{AppResponse, Executor},
-            Bech32App, Bench32AppExt, UnwrapError,
+            build_bech32_app, create_code, create_code_with_reply,
+            cw_multi_test::{next_block, AppResponse, Executor},
+            AppExt, Bech32App, Bench32AppExt, UnwrapError,
         },
+        storage::StorageOrder,
     },
-    std::collections::BTreeMap,
+    std::{collections::BTreeMap, str::FromStr},
     variable_manager_pkg::{
-        definitions::{Config, Variable},
-        msgs::{QueryMsg, RegisterVariableMsg, RemoveVariableMsg, UpdateOwnerMsg},
+        definitions::{
+            Config, ExpiringVariable, FromVariableManager, HookFailurePolicy, Modification,
+            Namespace, PendingTimelock, PendingUpdate, Proposal, Role, RoleGrant, ScheduledValue,
+            Subscription, Variable, VariableAction, VariableError, VariableHistoryEntry,
+            VariableMetadata, VariableReference, VariableSchema, VariableWithMeta,
+            DEFAULT_MAX_REFERENCE_DEPTH, DEFAULT_NAMESPACE, MAX_TIMELOCK_DELAY,
+        },
+        factory::{self, CreateManagerMsg, ManagerInfo},
+        helper::{factory_get_manager, factory_get_variable, get_variable, variable_manager_load},
+        msgs::{
+            CompareAndSwapMsg, CreateNamespaceMsg, ExecuteMsg, MigrateMsg, Operation, QueryMsg,
+            RegisterVariableMsg, RemoveByPrefixMsg, RemoveVariableMsg, RoleMsg,
+            ScheduleVariableMsg, SetMetadataMsg, SetSchemaMsg, SetTimelockMsg, SetVisibilityMsg,
+            SubscribeMsg, UnsubscribeMsg, UpdateNamespaceOwnersMsg, UpdateOwnerMsg,
+            VariableChangedHook,
+        },
     },
 };
 
@@ -28,26 +48,123 @@ fn startup() -> (Bech32App, Def) {
 
     let owner = app.generate_addr("owner");
 
-    let code_id = app.store_code(create_code(
+    let code_id = store_vm_code(&mut app);
+
+    let vm_addr = instantiate_vm(&mut app, code_id, &owner, "vm");
+
+    (app, Def { owner, vm_addr })
+}
+
+fn instantiate_vm(app: &mut Bech32App, code_id: u64, owner: &Addr, label: &str) -> Addr {
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &variable_manager_pkg::msgs::InstantiateMsg {
+            owners: vec![owner.to_string()],
+            threshold: None,
+            guardian: None,
+            max_reference_depth: None,
+        },
+        &[],
+        label,
+        Some(owner.to_string()),
+    )
+    .unwrap()
+}
+
+fn store_vm_code(app: &mut Bech32App) -> u64 {
+    let code = create_code_with_reply(
         variable_manager::contract::instantiate,
         variable_manager::contract::execute,
         variable_manager::contract::query,
+        variable_manager::contract::reply,
+    );
+
+    app.store_code(Box::new(
+        code.with_migrate(variable_manager::contract::migrate),
+    ))
+}
+
+fn store_factory_code(app: &mut Bech32App) -> u64 {
+    let code = create_code_with_reply(
+        variable_manager_factory::contract::instantiate,
+        variable_manager_factory::contract::execute,
+        variable_manager_factory::contract::query,
+        variable_manager_factory::contract::reply,
+    );
+
+    app.store_code(Box::new(
+        code.with_migrate(variable_manager_factory::contract::migrate),
+    ))
+}
+
+fn instantiate_factory(app: &mut Bech32App, owner: &Addr, manager_code_id: u64) -> Addr {
+    let code_id = store_factory_code(app);
+
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &factory::InstantiateMsg {
+            owners: vec![owner.to_string()],
+            manager_code_id,
+        },
+        &[],
+        "factory",
+        Some(owner.to_string()),
+    )
+    .unwrap()
+}
+
+fn deploy_legacy(
+    app: &mut Bech32App,
+    owner: &Addr,
+    variables: Vec<(&str, &Variable)>,
+    contract_version: Option<(&str, &str)>,
+) -> Addr {
+    let code_id = app.store_code(create_code(
+        mock_legacy::instantiate,
+        mock_legacy::execute,
+        mock_legacy::query,
     ));
 
-    let vm_addr = app
-        .instantiate_contract(
-            code_id,
-            owner.clone(),
-            &variable_manager_pkg::msgs::InstantiateMsg {
-                owners: vec![owner.to_string()],
-            },
-            &[],
-            "vm",
-            None,
-        )
-        .unwrap();
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &mock_legacy::InstantiateMsg {
+            owners: vec![owner.clone()],
+            variables: variables
+                .into_iter()
+                .map(|(key, variable)| (key.to_string(), variable.clone()))
+                .collect(),
+            contract_version: contract_version
+                .map(|(contract, version)| (contract.to_string(), version.to_string())),
+        },
+        &[],
+        "legacy",
+        Some(owner.to_string()),
+    )
+    .unwrap()
+}
 
-    (app, Def { owner, vm_addr })
+fn migrate(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    code_id: u64,
+    msg: MigrateMsg,
+) -> AnyResult<AppResponse> {
+    app.migrate_contract(sender.clone(), def.vm_addr.clone(), &msg, code_id)
+}
+
+fn deploy_consumer(app: &mut Bech32App, def: &Def, label: &str) -> Addr {
+    let code_id = app.store_code(create_code(
+        mock_consumer::instantiate,
+        mock_consumer::execute,
+        mock_consumer::query,
+    ));
+
+    app.instantiate_contract(code_id, def.owner.clone(), &Empty {}, &[], label, None)
+        .unwrap()
 }
 
 fn register_variable(
@@ -56,255 +173,2963 @@ fn register_variable(
     sender: &Addr,
     key: &str,
     variable: &Variable,
+) -> AnyResult<AppResponse> {
+    register_variable_in(app, def, sender, None, key, variable)
+}
+
+fn register_variable_in(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    namespace: Option<&str>,
+    key: &str,
+    variable: &Variable,
 ) -> AnyResult<AppResponse> {
     app.execute_contract(
         sender.clone(),
         def.vm_addr.clone(),
         &variable_manager_pkg::msgs::ExecuteMsg::RegisterVariable(RegisterVariableMsg {
+            namespace: namespace.map(|val| val.to_string()),
             key: key.to_string(),
             value: variable.clone(),
+            expires_at: None,
         }),
         &[],
     )
 }
 
-fn register_variables(
+fn register_expiring_variable(
     app: &mut Bech32App,
     def: &Def,
     sender: &Addr,
-    keys_values: Vec<(&str, &Variable)>,
+    key: &str,
+    variable: &Variable,
+    expires_at: Expiration,
 ) -> AnyResult<AppResponse> {
     app.execute_contract(
         sender.clone(),
         def.vm_addr.clone(),
-        &variable_manager_pkg::msgs::ExecuteMsg::RegisterVariables(
-            keys_values
-                .into_iter()
-                .map(|(k, v)| RegisterVariableMsg {
-                    key: k.to_string(),
-                    value: v.clone(),
-                })
-                .collect(),
-        ),
+        &ExecuteMsg::RegisterVariable(RegisterVariableMsg {
+            expires_at: Some(expires_at),
+            ..RegisterVariableMsg::new(key.to_string(), variable.clone())
+        }),
         &[],
     )
 }
 
-fn remove_variable(
+fn schedule_variable(
     app: &mut Bech32App,
     def: &Def,
     sender: &Addr,
     key: &str,
+    variable: &Variable,
+    activates_at: Scheduled,
 ) -> AnyResult<AppResponse> {
     app.execute_contract(
         sender.clone(),
         def.vm_addr.clone(),
-        &variable_manager_pkg::msgs::ExecuteMsg::RemoveVariable(RemoveVariableMsg {
+        &ExecuteMsg::ScheduleVariable(ScheduleVariableMsg {
+            namespace: None,
             key: key.to_string(),
+            value: variable.clone(),
+            activates_at,
         }),
         &[],
     )
 }
 
-fn update_variable(
+fn cancel_scheduled(
     app: &mut Bech32App,
     def: &Def,
     sender: &Addr,
     key: &str,
-    variable: &Variable,
+    activates_at: Scheduled,
 ) -> AnyResult<AppResponse> {
     app.execute_contract(
         sender.clone(),
         def.vm_addr.clone(),
-        &variable_manager_pkg::msgs::ExecuteMsg::UpdateVariable(RegisterVariableMsg {
+        &ExecuteMsg::CancelScheduled {
+            namespace: None,
             key: key.to_string(),
-            value: variable.clone(),
-        }),
+            activates_at,
+        },
         &[],
     )
 }
 
-fn update_variables(
+fn freeze_variable(
     app: &mut Bech32App,
     def: &Def,
     sender: &Addr,
-    keys_values: Vec<(&str, &Variable)>,
+    key: &str,
+    until: Option<Expiration>,
 ) -> AnyResult<AppResponse> {
     app.execute_contract(
         sender.clone(),
         def.vm_addr.clone(),
-        &variable_manager_pkg::msgs::ExecuteMsg::UpdateVariables(
-            keys_values
-                .into_iter()
-                .map(|(k, v)| RegisterVariableMsg {
-                    key: k.to_string(),
-                    value: v.clone(),
-                })
-                .collect(),
-        ),
+        &ExecuteMsg::FreezeVariable {
+            namespace: None,
+            key: key.to_string(),
+            until,
+        },
         &[],
     )
 }
 
-fn update_owners(
+fn unfreeze_variable(
     app: &mut Bech32App,
     def: &Def,
     sender: &Addr,
-    add: Option<&[&Addr]>,
-    remove: Option<&[&Addr]>,
+    key: &str,
 ) -> AnyResult<AppResponse> {
     app.execute_contract(
         sender.clone(),
         def.vm_addr.clone(),
-        &variable_manager_pkg::msgs::ExecuteMsg::UpdateOwners(UpdateOwnerMsg {
-            add: add.map(|add| add.iter().map(|a| a.to_string()).collect()),
-            remove: remove.map(|remove| remove.iter().map(|a| a.to_string()).collect()),
-        }),
+        &ExecuteMsg::UnfreezeVariable {
+            namespace: None,
+            key: key.to_string(),
+        },
         &[],
     )
 }
 
-fn qy_config(app: &Bech32App, def: &Def) -> AnyResult<Config> {
-    Ok(app
-        .wrap()
-        .query_wasm_smart(&def.vm_addr, &QueryMsg::Config {})?)
+fn nominate_owner(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    address: &Addr,
+    expires_at: Option<Expiration>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::NominateOwner {
+            address: address.to_string(),
+            expires_at,
+        },
+        &[],
+    )
 }
 
-fn qy_variable(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Variable> {
-    Ok(app.wrap().query_wasm_smart(
-        &def.vm_addr,
-        &QueryMsg::GetVariable {
-            key: key.to_string(),
+fn revoke_nomination(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    address: &Addr,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::RevokeNomination {
+            address: address.to_string(),
         },
-    )?)
+        &[],
+    )
 }
 
-fn qy_variables(app: &Bech32App, def: &Def, key: &[&str]) -> AnyResult<BTreeMap<String, Variable>> {
-    Ok(app.wrap().query_wasm_smart(
-        &def.vm_addr,
-        &QueryMsg::GetVariables {
-            keys: key.into_iter().map(|val| val.to_string()).collect(),
-        },
-    )?)
+fn accept_ownership(app: &mut Bech32App, def: &Def, sender: &Addr) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::AcceptOwnership {},
+        &[],
+    )
 }
 
-fn qy_all_variables(
-    app: &Bech32App,
+fn set_max_reference_depth(
+    app: &mut Bech32App,
     def: &Def,
-    start_after: Option<&str>,
-    limit: Option<u32>,
-) -> AnyResult<Vec<(String, Variable)>> {
-    Ok(app.wrap().query_wasm_smart(
-        &def.vm_addr,
-        &QueryMsg::AllVariables {
-            start_after: start_after.map(|val| val.to_string()),
-            limit,
+    sender: &Addr,
+    max_reference_depth: u32,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::SetMaxReferenceDepth {
+            max_reference_depth,
         },
-    )?)
+        &[],
+    )
 }
 
-#[test]
-#[rustfmt::skip]
-fn integration() {
-    let (mut app, mut def) = startup();
+fn create_manager(
+    app: &mut Bech32App,
+    factory_addr: &Addr,
+    sender: &Addr,
+    protocol: &str,
+    owner: &Addr,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        factory_addr.clone(),
+        &factory::ExecuteMsg::CreateManager(CreateManagerMsg {
+            protocol: protocol.to_string(),
+            msg: variable_manager_pkg::msgs::InstantiateMsg {
+                owners: vec![owner.to_string()],
+                threshold: None,
+                guardian: None,
+                max_reference_depth: None,
+            },
+            admin: Some(owner.to_string()),
+        }),
+        &[],
+    )
+}
 
-    let mut var_1 = Variable::String("var_1".to_string());
-    let mut var_2 = Variable::Uint128(100u128.into());
-    let mut var_3 = Variable::Binary(Binary(vec![1, 2, 3, 4]));
-    let var_4 = Variable::Decimal("1.5".into_decimal());
+fn set_manager_code_id(
+    app: &mut Bech32App,
+    factory_addr: &Addr,
+    sender: &Addr,
+    code_id: u64,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        factory_addr.clone(),
+        &factory::ExecuteMsg::SetManagerCodeId { code_id },
+        &[],
+    )
+}
 
-    let random_addr = app.generate_addr("random_addr");
+fn update_factory_owners(
+    app: &mut Bech32App,
+    factory_addr: &Addr,
+    sender: &Addr,
+    add: Option<Vec<&Addr>>,
+    remove: Option<Vec<&Addr>>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        factory_addr.clone(),
+        &factory::ExecuteMsg::UpdateOwners {
+            add: add.map(|add| add.into_iter().map(|addr| addr.to_string()).collect()),
+            remove: remove.map(|remove| remove.into_iter().map(|addr| addr.to_string()).collect()),
+        },
+        &[],
+    )
+}
 
-    // --- Err Invalid owner ---
-    {
-        register_variable(&mut app, &def, &random_addr, "var_1", &var_1).unwrap_err_contains("not an owner");
-    }
+fn migrate_factory(
+    app: &mut Bech32App,
+    factory_addr: &Addr,
+    sender: &Addr,
+    code_id: u64,
+) -> AnyResult<AppResponse> {
+    app.migrate_contract(
+        sender.clone(),
+        factory_addr.clone(),
+        &factory::MigrateMsg {},
+        code_id,
+    )
+}
 
-    // --- Ok  register variable ---
-    {
-        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
-        register_variable(&mut app, &def, &def.owner, "var_2", &var_2).unwrap();
-    }
+fn qy_factory_config(app: &Bech32App, factory_addr: &Addr) -> AnyResult<factory::Config> {
+    Ok(app
+        .wrap()
+        .query_wasm_smart(factory_addr, &factory::QueryMsg::Config {})?)
+}
 
-    // --- Ok  register variables ---
-    {
-        register_variables(&mut app, &def, &def.owner, vec![("var_3", &var_3), ("var_4", &var_4)]).unwrap();
-    }
+fn pause(app: &mut Bech32App, def: &Def, sender: &Addr) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Pause {},
+        &[],
+    )
+}
 
-    // --- Err variable alredy registered ---
-    {
-        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap_err_contains("already registered");
-        register_variables(&mut app, &def, &def.owner, vec![("var_3", &var_3), ("var_4", &var_4)]).unwrap_err_contains("already registered");
-    }
+fn unpause(app: &mut Bech32App, def: &Def, sender: &Addr) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Unpause {},
+        &[],
+    )
+}
 
-    // --- Ok  remove variable ---
-    {
-        remove_variable(&mut app, &def, &def.owner, "var_1").unwrap();
-    }
+fn set_guardian(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    guardian: Option<&Addr>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::SetGuardian {
+            guardian: guardian.map(|guardian| guardian.to_string()),
+        },
+        &[],
+    )
+}
 
-    // --- Err remove unexisting variable ---
-    {
-        remove_variable(&mut app, &def, &def.owner, "var_5").unwrap_err_contains("Key not found: var_5");
-    }
+fn set_metadata(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    description: Option<&str>,
+    tags: Option<Vec<&str>>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::SetMetadata(SetMetadataMsg {
+            namespace: None,
+            key: key.to_string(),
+            description: description.map(|val| val.to_string()),
+            tags: tags.map(|tags| tags.into_iter().map(|tag| tag.to_string()).collect()),
+        }),
+        &[],
+    )
+}
+
+fn prune_expired(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    limit: Option<u32>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::PruneExpired { limit },
+        &[],
+    )
+}
+
+fn register_variables(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    keys_values: Vec<(&str, &Variable)>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::RegisterVariables(
+            keys_values
+                .into_iter()
+                .map(|(k, v)| RegisterVariableMsg {
+                    namespace: None,
+                    key: k.to_string(),
+                    value: v.clone(),
+                    expires_at: None,
+                })
+                .collect(),
+        ),
+        &[],
+    )
+}
+
+fn remove_variable(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+) -> AnyResult<AppResponse> {
+    remove_variable_in(app, def, sender, None, key)
+}
+
+fn remove_variable_in(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    namespace: Option<&str>,
+    key: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::RemoveVariable(RemoveVariableMsg {
+            namespace: namespace.map(|val| val.to_string()),
+            key: key.to_string(),
+        }),
+        &[],
+    )
+}
+
+fn remove_variables(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    keys: Vec<&str>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::RemoveVariables(
+            keys.into_iter()
+                .map(|key| RemoveVariableMsg {
+                    namespace: None,
+                    key: key.to_string(),
+                })
+                .collect(),
+        ),
+        &[],
+    )
+}
+
+fn remove_by_prefix(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    prefix: &str,
+    limit: Option<u32>,
+) -> AnyResult<AppResponse> {
+    remove_by_prefix_after(app, def, sender, prefix, None, limit)
+}
+
+fn remove_by_prefix_after(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    prefix: &str,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::RemoveByPrefix(RemoveByPrefixMsg {
+            namespace: None,
+            prefix: prefix.to_string(),
+            start_after: start_after.map(|val| val.to_string()),
+            limit,
+        }),
+        &[],
+    )
+}
+
+fn update_variable(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    variable: &Variable,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::UpdateVariable(RegisterVariableMsg {
+            namespace: None,
+            key: key.to_string(),
+            value: variable.clone(),
+            expires_at: None,
+        }),
+        &[],
+    )
+}
+
+fn update_variables(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    keys_values: Vec<(&str, &Variable)>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::UpdateVariables(
+            keys_values
+                .into_iter()
+                .map(|(k, v)| RegisterVariableMsg {
+                    namespace: None,
+                    key: k.to_string(),
+                    value: v.clone(),
+                    expires_at: None,
+                })
+                .collect(),
+        ),
+        &[],
+    )
+}
+
+fn update_owners(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    add: Option<&[&Addr]>,
+    remove: Option<&[&Addr]>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::UpdateOwners(UpdateOwnerMsg {
+            add: add.map(|add| add.iter().map(|a| a.to_string()).collect()),
+            remove: remove.map(|remove| remove.iter().map(|a| a.to_string()).collect()),
+            threshold: None,
+        }),
+        &[],
+    )
+}
+
+fn update_threshold(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    threshold: u64,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::UpdateOwners(UpdateOwnerMsg {
+            add: None,
+            remove: None,
+            threshold: Some(threshold),
+        }),
+        &[],
+    )
+}
+
+fn vote(app: &mut Bech32App, def: &Def, sender: &Addr, proposal_id: u64) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Vote { proposal_id },
+        &[],
+    )
+}
+
+fn execute_proposal(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    proposal_id: u64,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::ExecuteProposal { proposal_id },
+        &[],
+    )
+}
+
+fn close_proposal(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    proposal_id: u64,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::CloseProposal { proposal_id },
+        &[],
+    )
+}
+
+fn set_visibility(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    public: bool,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::SetVisibility(SetVisibilityMsg {
+            namespace: None,
+            key: key.to_string(),
+            public,
+        }),
+        &[],
+    )
+}
+
+fn read_variables(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    keys: &[&str],
+) -> AnyResult<BTreeMap<String, Variable>> {
+    let res = app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::ReadVariables {
+            namespace: None,
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        },
+        &[],
+    )?;
+
+    Ok(serde_json::from_slice(&res.data.unwrap())?)
+}
+
+fn set_schema(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    schema: Option<VariableSchema>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::SetSchema(SetSchemaMsg {
+            namespace: None,
+            key: key.to_string(),
+            schema,
+        }),
+        &[],
+    )
+}
+
+fn grant_role(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    address: &Addr,
+    role: Role,
+    prefix: &str,
+) -> AnyResult<AppResponse> {
+    grant_role_in(app, def, sender, address, role, None, prefix)
+}
+
+fn grant_role_in(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    address: &Addr,
+    role: Role,
+    namespace: Option<&str>,
+    prefix: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::GrantRole(RoleMsg {
+            address: address.to_string(),
+            role,
+            namespace: namespace.map(|val| val.to_string()),
+            prefix: prefix.to_string(),
+        }),
+        &[],
+    )
+}
+
+fn revoke_role(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    address: &Addr,
+    role: Role,
+    prefix: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &variable_manager_pkg::msgs::ExecuteMsg::RevokeRole(RoleMsg {
+            address: address.to_string(),
+            role,
+            namespace: None,
+            prefix: prefix.to_string(),
+        }),
+        &[],
+    )
+}
+
+fn set_timelock(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    delay: Option<u64>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::SetTimelock(SetTimelockMsg {
+            namespace: None,
+            key: key.to_string(),
+            delay,
+        }),
+        &[],
+    )
+}
+
+fn propose_update(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    variable: &Variable,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::ProposeUpdate(RegisterVariableMsg {
+            namespace: None,
+            key: key.to_string(),
+            value: variable.clone(),
+            expires_at: None,
+        }),
+        &[],
+    )
+}
+
+fn execute_pending(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::ExecutePending {
+            namespace: None,
+            key: key.to_string(),
+        },
+        &[],
+    )
+}
+
+fn cancel_pending(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::CancelPending {
+            namespace: None,
+            key: key.to_string(),
+        },
+        &[],
+    )
+}
+
+fn batch(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    operations: Vec<Operation>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Batch(operations),
+        &[],
+    )
+}
+
+fn upsert(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    value: &Variable,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Upsert(RegisterVariableMsg::new(key.to_string(), value.clone())),
+        &[],
+    )
+}
+
+fn compare_and_swap(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    key: &str,
+    expected: Option<&Variable>,
+    new: &Variable,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::CompareAndSwap(CompareAndSwapMsg {
+            namespace: None,
+            key: key.to_string(),
+            expected: expected.cloned(),
+            new: new.clone(),
+        }),
+        &[],
+    )
+}
+
+fn create_namespace(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    namespace: &str,
+    owners: &[&Addr],
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::CreateNamespace(CreateNamespaceMsg {
+            namespace: namespace.to_string(),
+            owners: owners.iter().map(|a| a.to_string()).collect(),
+        }),
+        &[],
+    )
+}
+
+fn delete_namespace(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    namespace: &str,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::DeleteNamespace {
+            namespace: namespace.to_string(),
+        },
+        &[],
+    )
+}
+
+fn update_namespace_owners(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    namespace: &str,
+    add: Option<&[&Addr]>,
+    remove: Option<&[&Addr]>,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::UpdateNamespaceOwners(UpdateNamespaceOwnersMsg {
+            namespace: namespace.to_string(),
+            add: add.map(|add| add.iter().map(|a| a.to_string()).collect()),
+            remove: remove.map(|remove| remove.iter().map(|a| a.to_string()).collect()),
+        }),
+        &[],
+    )
+}
+
+fn subscribe(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    prefix: &str,
+    contract: &Addr,
+    on_error: HookFailurePolicy,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Subscribe(SubscribeMsg {
+            namespace: None,
+            prefix: prefix.to_string(),
+            contract: contract.to_string(),
+            on_error,
+        }),
+        &[],
+    )
+}
+
+fn unsubscribe(
+    app: &mut Bech32App,
+    def: &Def,
+    sender: &Addr,
+    prefix: &str,
+    contract: &Addr,
+) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        sender.clone(),
+        def.vm_addr.clone(),
+        &ExecuteMsg::Unsubscribe(UnsubscribeMsg {
+            namespace: None,
+            prefix: prefix.to_string(),
+            contract: contract.to_string(),
+        }),
+        &[],
+    )
+}
+
+fn qy_config(app: &Bech32App, def: &Def) -> AnyResult<Config> {
+    Ok(app
+        .wrap()
+        .query_wasm_smart(&def.vm_addr, &QueryMsg::Config {})?)
+}
+
+fn qy_variable(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Variable> {
+    qy_variable_in(app, def, None, key)
+}
+
+fn qy_variable_in(
+    app: &Bech32App,
+    def: &Def,
+    namespace: Option<&str>,
+    key: &str,
+) -> AnyResult<Variable> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::GetVariable {
+            namespace: namespace.map(|val| val.to_string()),
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_variable_raw(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Variable> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::GetVariableRaw {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_manager(app: &Bech32App, factory_addr: &Addr, protocol: &str) -> AnyResult<ManagerInfo> {
+    Ok(app.wrap().query_wasm_smart(
+        factory_addr,
+        &factory::QueryMsg::Manager {
+            protocol: protocol.to_string(),
+        },
+    )?)
+}
+
+fn qy_managers(
+    app: &Bech32App,
+    factory_addr: &Addr,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> AnyResult<Vec<ManagerInfo>> {
+    Ok(app.wrap().query_wasm_smart(
+        factory_addr,
+        &factory::QueryMsg::Managers {
+            start_after: start_after.map(|val| val.to_string()),
+            limit,
+        },
+    )?)
+}
+
+fn qy_variables(app: &Bech32App, def: &Def, key: &[&str]) -> AnyResult<BTreeMap<String, Variable>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::GetVariables {
+            namespace: None,
+            keys: key.iter().map(|val| val.to_string()).collect(),
+            allow_missing: None,
+        },
+    )?)
+}
+
+fn qy_all_variables(
+    app: &Bech32App,
+    def: &Def,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> AnyResult<Vec<(String, Variable)>> {
+    qy_all_variables_in(app, def, None, start_after, limit)
+}
+
+fn qy_all_variables_in(
+    app: &Bech32App,
+    def: &Def,
+    namespace: Option<&str>,
+    start_after: Option<&str>,
+    limit: Option<u32>,
+) -> AnyResult<Vec<(String, Variable)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::AllVariables {
+            namespace: namespace.map(|val| val.to_string()),
+            start_after: start_after.map(|val| val.to_string()),
+            limit,
+        },
+    )?)
+}
+
+fn qy_variables_by_prefix(
+    app: &Bech32App,
+    def: &Def,
+    prefix: &str,
+    start_after: Option<&str>,
+    order: StorageOrder,
+) -> AnyResult<Vec<(String, Variable)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::VariablesByPrefix {
+            namespace: None,
+            prefix: prefix.to_string(),
+            start_after: start_after.map(|val| val.to_string()),
+            limit: Some(2),
+            order: Some(order),
+        },
+    )?)
+}
+
+fn qy_variables_in_range(
+    app: &Bech32App,
+    def: &Def,
+    min: Option<&str>,
+    max: Option<&str>,
+    order: StorageOrder,
+) -> AnyResult<Vec<(String, Variable)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::VariablesInRange {
+            namespace: None,
+            min: min.map(|val| val.to_string()),
+            max: max.map(|val| val.to_string()),
+            start_after: None,
+            limit: None,
+            order: Some(order),
+        },
+    )?)
+}
+
+fn qy_variable_history(
+    app: &Bech32App,
+    def: &Def,
+    key: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> AnyResult<Vec<(u64, VariableHistoryEntry)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::VariableHistory {
+            namespace: None,
+            key: key.to_string(),
+            start_after,
+            limit,
+        },
+    )?)
+}
+
+fn qy_variable_at_height(
+    app: &Bech32App,
+    def: &Def,
+    key: &str,
+    height: u64,
+) -> AnyResult<Option<Variable>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::VariableAtHeight {
+            namespace: None,
+            key: key.to_string(),
+            height,
+        },
+    )?)
+}
+
+fn qy_roles(app: &Bech32App, def: &Def, address: &Addr) -> AnyResult<Vec<RoleGrant>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Roles {
+            address: address.to_string(),
+        },
+    )?)
+}
+
+fn qy_timelock(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Option<u64>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Timelock {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_pending_timelock(
+    app: &Bech32App,
+    def: &Def,
+    key: &str,
+) -> AnyResult<Option<PendingTimelock>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::PendingTimelock {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_pending_update(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Option<PendingUpdate>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::PendingUpdate {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_proposal(app: &Bech32App, def: &Def, proposal_id: u64) -> AnyResult<Proposal> {
+    Ok(app
+        .wrap()
+        .query_wasm_smart(&def.vm_addr, &QueryMsg::Proposal { proposal_id })?)
+}
+
+fn qy_all_pending_updates(app: &Bech32App, def: &Def) -> AnyResult<Vec<(String, PendingUpdate)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::AllPendingUpdates {
+            namespace: None,
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+fn qy_proposals(app: &Bech32App, def: &Def) -> AnyResult<Vec<(u64, Proposal)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Proposals {
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+fn qy_schema(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Option<VariableSchema>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Schema {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_subscriptions(app: &Bech32App, def: &Def) -> AnyResult<Vec<Subscription>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Subscriptions {
+            namespace: None,
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+fn qy_expiring_variables(
+    app: &Bech32App,
+    def: &Def,
+    within: Duration,
+    start_after: Option<ExpiringVariable>,
+) -> AnyResult<Vec<ExpiringVariable>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::ExpiringVariables {
+            within,
+            start_after,
+            limit: Some(2),
+        },
+    )?)
+}
+
+fn qy_schedule(app: &Bech32App, def: &Def, key: &str) -> AnyResult<Vec<ScheduledValue>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Schedule {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_variable_with_meta(app: &Bech32App, def: &Def, key: &str) -> AnyResult<VariableWithMeta> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::GetVariableWithMeta {
+            namespace: None,
+            key: key.to_string(),
+        },
+    )?)
+}
+
+fn qy_all_variables_with_meta(
+    app: &Bech32App,
+    def: &Def,
+) -> AnyResult<Vec<(String, VariableWithMeta)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::AllVariablesWithMeta {
+            namespace: None,
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+fn qy_variables_by_tag(
+    app: &Bech32App,
+    def: &Def,
+    tag: &str,
+    start_after: Option<&str>,
+) -> AnyResult<Vec<(String, Variable)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::VariablesByTag {
+            namespace: None,
+            tag: tag.to_string(),
+            start_after: start_after.map(|val| val.to_string()),
+            limit: Some(2),
+        },
+    )?)
+}
+
+fn qy_frozen_variables(app: &Bech32App, def: &Def) -> AnyResult<Vec<(String, Expiration)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::FrozenVariables {
+            namespace: None,
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+fn qy_owner_nominations(app: &Bech32App, def: &Def) -> AnyResult<Vec<(Addr, Expiration)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::OwnerNominations {
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+fn qy_consumer_hooks(app: &Bech32App, consumer: &Addr) -> AnyResult<Vec<VariableChangedHook>> {
+    Ok(app.wrap().query_wasm_smart(consumer, &Empty {})?)
+}
+
+fn qy_namespaces(app: &Bech32App, def: &Def) -> AnyResult<Vec<(String, Namespace)>> {
+    Ok(app.wrap().query_wasm_smart(
+        &def.vm_addr,
+        &QueryMsg::Namespaces {
+            start_after: None,
+            limit: None,
+        },
+    )?)
+}
+
+#[test]
+#[rustfmt::skip]
+fn integration() {
+    let (mut app, mut def) = startup();
+
+    let mut var_1 = Variable::String("var_1".to_string());
+    let mut var_2 = Variable::Uint128(100u128.into());
+    let mut var_3 = Variable::Binary(Binary(vec![1, 2, 3, 4]));
+    let var_4 = Variable::Decimal("1.5".into_decimal());
+
+    let random_addr = app.generate_addr("random_addr");
+
+    // --- Err Invalid owner ---
+    {
+        register_variable(&mut app, &def, &random_addr, "var_1", &var_1).unwrap_err_contains("not an owner");
+    }
+
+    // --- Ok  register variable ---
+    {
+        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
+        register_variable(&mut app, &def, &def.owner, "var_2", &var_2).unwrap();
+    }
+
+    // --- Ok  register variables ---
+    {
+        register_variables(&mut app, &def, &def.owner, vec![("var_3", &var_3), ("var_4", &var_4)]).unwrap();
+    }
+
+    // --- Err variable alredy registered ---
+    {
+        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap_err_contains("already registered");
+        register_variables(&mut app, &def, &def.owner, vec![("var_3", &var_3), ("var_4", &var_4)]).unwrap_err_contains("already registered");
+    }
+
+    // --- Ok  remove variable ---
+    {
+        remove_variable(&mut app, &def, &def.owner, "var_1").unwrap();
+    }
+
+    // --- Err remove unexisting variable ---
+    {
+        remove_variable(&mut app, &def, &def.owner, "var_5").unwrap_err_contains("Key not found: var_5");
+    }
+
+    // --- OK  update variable ---
+    {
+        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
+        var_1 = Variable::String("var_1_updated".to_string());
+        update_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
+    }
+
+    // --- Err update unexisting variable ---
+    {
+        update_variable(&mut app, &def, &def.owner, "var_10", &var_1).unwrap_err_contains("Key not found: var_10");
+    }
+
+    // --- OK  update variables ---
+    {
+        var_2 = Variable::Uint128(200u128.into());
+        var_3 = Variable::Binary(Binary(vec![4, 3, 2, 1]));
+        update_variables(&mut app, &def, &def.owner, vec![("var_2", &var_2), ("var_3", &var_3)]).unwrap(); 
+    }
+
+    // --- Err update unexisting variables ---
+    {
+        update_variables(&mut app, &def, &def.owner, vec![("var_20", &var_2), ("var_3", &var_3)]).unwrap_err_contains("Key not found: var_20"); 
+    }
+
+    // --- Err update owner ---
+    {
+        // sanity check
+        update_owners(&mut app, &def, &random_addr, None, None).unwrap_err_contains("not an owner");
+        
+        update_owners(&mut app, &def, &def.owner, None, Some(&[&def.owner])).unwrap_err_contains("Invalid 0 owners. Needed at least 1");
+        update_owners(&mut app, &def, &def.owner, Some(&[&def.owner]), None).unwrap_err_contains("is alredy a owner");
+        let old_owner = def.owner.clone();
+        def.owner = app.generate_addr("new_owner");
+        update_owners(&mut app, &def, &old_owner, Some(&[&def.owner]), None).unwrap();
+        accept_ownership(&mut app, &def, &def.owner).unwrap();
+        update_owners(&mut app, &def, &old_owner, None, Some(&[&old_owner])).unwrap();
+    }
+
+    // --- Assert config ---
+    {
+        let config = qy_config(&app, &def).unwrap();
+        assert_eq!(config.owners.len(), 1);
+        assert_eq!(config.owners[0], def.owner);
+    }
+
+    // --- Assert variables ---
+    {
+        assert_eq!(var_1, qy_variable(&app, &def, "var_1").unwrap());
+        assert_eq!(
+            BTreeMap::from([("var_1".to_string(), var_1.clone()), ("var_2".to_string(), var_2.clone())]),
+            qy_variables(&app, &def, &["var_1", "var_2"]).unwrap()
+        );
+        assert_eq!(
+           vec![("var_1".to_string(), var_1.clone()), ("var_2".to_string(), var_2.clone())],
+            qy_all_variables(&app, &def, None, Some(2)).unwrap()
+        );
+        assert_eq!(
+            vec![("var_3".to_string(), var_3.clone()), ("var_4".to_string(), var_4.clone())],
+             qy_all_variables(&app, &def, Some("var_2"), None).unwrap()
+         );
+    }
+
+}
+
+#[test]
+#[rustfmt::skip]
+fn history() {
+    let (mut app, def) = startup();
+
+    let var_1 = Variable::Uint128(100u128.into());
+    let var_2 = Variable::Uint128(200u128.into());
+
+    let height_register = app.block_info().height;
+    register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
+
+    app.update_block(next_block);
+    let height_update = app.block_info().height;
+    update_variable(&mut app, &def, &def.owner, "var_1", &var_2).unwrap();
+
+    app.update_block(next_block);
+    let height_remove = app.block_info().height;
+    remove_variable(&mut app, &def, &def.owner, "var_1").unwrap();
+
+    // --- Assert history ---
+    {
+        let history = qy_variable_history(&app, &def, "var_1", None, None).unwrap();
+
+        assert_eq!(history.len(), 3);
+
+        assert_eq!(history[0].1.action, VariableAction::Register);
+        assert_eq!(history[0].1.value, Some(var_1.clone()));
+        assert_eq!(history[0].1.height, height_register);
+        assert_eq!(history[0].1.sender, def.owner);
+
+        assert_eq!(history[1].1.action, VariableAction::Update);
+        assert_eq!(history[1].1.value, Some(var_2.clone()));
+        assert_eq!(history[1].1.height, height_update);
+
+        assert_eq!(history[2].1.action, VariableAction::Remove);
+        assert_eq!(history[2].1.value, None);
+        assert_eq!(history[2].1.height, height_remove);
+
+        let paginated = qy_variable_history(&app, &def, "var_1", Some(history[0].0), Some(1)).unwrap();
+        assert_eq!(paginated, vec![history[1].clone()]);
+    }
+
+    // --- Assert value at height ---
+    {
+        assert_eq!(qy_variable_at_height(&app, &def, "var_1", height_register - 1).unwrap(), None);
+        assert_eq!(qy_variable_at_height(&app, &def, "var_1", height_register).unwrap(), Some(var_1));
+        assert_eq!(qy_variable_at_height(&app, &def, "var_1", height_update).unwrap(), Some(var_2));
+        assert_eq!(qy_variable_at_height(&app, &def, "var_1", height_remove).unwrap(), None);
+        assert_eq!(qy_variable_at_height(&app, &def, "var_1", u64::MAX).unwrap(), None);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn roles() {
+    let (mut app, def) = startup();
+
+    let oracle_admin = app.generate_addr("oracle_admin");
+    let oracle_writer = app.generate_addr("oracle_writer");
+    let reader = app.generate_addr("reader");
+
+    let var_1 = Variable::Decimal("1.5".into_decimal());
+
+    // --- Err grant role from not owner ---
+    {
+        grant_role(&mut app, &def, &oracle_admin, &oracle_admin, Role::Admin, "oracle.").unwrap_err_contains("has no role allowing to modify oracle.");
+    }
+
+    // --- Ok  grant roles ---
+    {
+        grant_role(&mut app, &def, &def.owner, &oracle_admin, Role::Admin, "oracle.").unwrap();
+        grant_role(&mut app, &def, &def.owner, &oracle_admin, Role::Admin, "oracle.").unwrap_err_contains("alredy granted");
+        grant_role(&mut app, &def, &oracle_admin, &oracle_writer, Role::Writer, "oracle.price.").unwrap();
+        grant_role(&mut app, &def, &oracle_admin, &reader, Role::Reader, "market.").unwrap_err_contains("has no role allowing to modify market.");
+        grant_role(&mut app, &def, &def.owner, &reader, Role::Reader, "").unwrap();
+
+        assert_eq!(qy_roles(&app, &def, &oracle_writer).unwrap(), vec![RoleGrant { role: Role::Writer, namespace: DEFAULT_NAMESPACE.to_string(), prefix: "oracle.price.".to_string() }]);
+    }
+
+    // --- Writes scoped by prefix ---
+    {
+        register_variable(&mut app, &def, &oracle_writer, "oracle.price.atom", &var_1).unwrap();
+        register_variable(&mut app, &def, &oracle_writer, "oracle.source", &var_1).unwrap_err_contains("not an owner");
+        register_variable(&mut app, &def, &oracle_admin, "oracle.source", &var_1).unwrap();
+        register_variable(&mut app, &def, &oracle_admin, "market.fee", &var_1).unwrap_err_contains("not an owner");
+        register_variable(&mut app, &def, &reader, "market.fee", &var_1).unwrap_err_contains("not an owner");
+        register_variables(&mut app, &def, &oracle_writer, vec![("oracle.price.osmo", &var_1), ("oracle.other", &var_1)]).unwrap_err_contains("oracle.other");
+        update_variable(&mut app, &def, &oracle_writer, "oracle.price.atom", &var_1).unwrap();
+        remove_variable(&mut app, &def, &oracle_writer, "oracle.price.atom").unwrap();
+        update_owners(&mut app, &def, &oracle_admin, Some(&[&oracle_admin]), None).unwrap_err_contains("not an owner");
+    }
+
+    // --- Revoke roles ---
+    {
+        revoke_role(&mut app, &def, &oracle_admin, &oracle_writer, Role::Writer, "oracle.price.").unwrap();
+        revoke_role(&mut app, &def, &oracle_admin, &oracle_writer, Role::Writer, "oracle.price.").unwrap_err_contains("not granted");
+        register_variable(&mut app, &def, &oracle_writer, "oracle.price.atom", &var_1).unwrap_err_contains("not an owner");
+        assert_eq!(qy_roles(&app, &def, &oracle_writer).unwrap(), vec![]);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn timelock() {
+    let (mut app, def) = startup();
+
+    let random_addr = app.generate_addr("random_addr");
+    let writer = app.generate_addr("writer");
+
+    let treasury = Variable::Addr(app.generate_addr("treasury"));
+    let new_treasury = Variable::Addr(app.generate_addr("new_treasury"));
+
+    register_variable(&mut app, &def, &def.owner, "treasury", &treasury).unwrap();
+
+    // --- Timelocked key can't be changed directly ---
+    {
+        set_timelock(&mut app, &def, &random_addr, "treasury", Some(100)).unwrap_err_contains("not an owner");
+        set_timelock(&mut app, &def, &def.owner, "treasury", Some(MAX_TIMELOCK_DELAY + 1)).unwrap_err_contains("too long");
+        set_timelock(&mut app, &def, &def.owner, "treasury", Some(u64::MAX)).unwrap_err_contains("too long");
+
+        grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "").unwrap();
+        set_timelock(&mut app, &def, &writer, "treasury", Some(100)).unwrap_err_contains("has no role allowing to modify");
+
+        set_timelock(&mut app, &def, &def.owner, "treasury", Some(100)).unwrap();
+        update_variable(&mut app, &def, &def.owner, "treasury", &new_treasury).unwrap_err_contains("is timelocked");
+        remove_variable(&mut app, &def, &def.owner, "treasury").unwrap_err_contains("is timelocked");
+    }
+
+    // --- Propose and cancel ---
+    {
+        propose_update(&mut app, &def, &random_addr, "treasury", &new_treasury).unwrap_err_contains("not an owner");
+        propose_update(&mut app, &def, &def.owner, "treasury", &new_treasury).unwrap();
+        propose_update(&mut app, &def, &def.owner, "treasury", &new_treasury).unwrap_err_contains("alredy exists");
+        cancel_pending(&mut app, &def, &random_addr, "treasury").unwrap_err_contains("not an owner");
+        cancel_pending(&mut app, &def, &def.owner, "treasury").unwrap();
+        assert_eq!(qy_pending_update(&app, &def, "treasury").unwrap(), None);
+        execute_pending(&mut app, &def, &random_addr, "treasury").unwrap_err_contains("Pending update not found");
+    }
+
+    // --- Propose and execute ---
+    {
+        propose_update(&mut app, &def, &def.owner, "treasury", &new_treasury).unwrap();
+
+        let pending = qy_pending_update(&app, &def, "treasury").unwrap().unwrap();
+        assert_eq!(pending.value, new_treasury);
+        assert_eq!(pending.eta, app.block_info().time.plus_seconds(100));
+
+        app.increase_time(99);
+        execute_pending(&mut app, &def, &random_addr, "treasury").unwrap_err_contains("not executable before");
+        assert_eq!(qy_variable(&app, &def, "treasury").unwrap(), treasury);
+
+        app.increase_time(1);
+        execute_pending(&mut app, &def, &random_addr, "treasury").unwrap();
+        assert_eq!(qy_variable(&app, &def, "treasury").unwrap(), new_treasury);
+        assert_eq!(qy_pending_update(&app, &def, "treasury").unwrap(), None);
+    }
+
+    // --- Raise timelock ---
+    {
+        set_timelock(&mut app, &def, &def.owner, "treasury", Some(200)).unwrap();
+        assert_eq!(qy_timelock(&app, &def, "treasury").unwrap(), Some(200));
+        assert_eq!(qy_pending_timelock(&app, &def, "treasury").unwrap(), None);
+    }
+
+    // --- Remove timelock ---
+    {
+        let eta = app.block_info().time.plus_seconds(200);
+
+        set_timelock(&mut app, &def, &def.owner, "treasury", None).unwrap();
+        assert_eq!(qy_timelock(&app, &def, "treasury").unwrap(), Some(200));
+        assert_eq!(qy_pending_timelock(&app, &def, "treasury").unwrap(), Some(PendingTimelock { delay: None, eta }));
+        update_variable(&mut app, &def, &def.owner, "treasury", &treasury).unwrap_err_contains("is timelocked");
+
+        app.increase_time(199);
+        set_timelock(&mut app, &def, &def.owner, "treasury", None).unwrap_err_contains("not executable before");
+        update_variable(&mut app, &def, &def.owner, "treasury", &treasury).unwrap_err_contains("is timelocked");
+
+        app.increase_time(1);
+        set_timelock(&mut app, &def, &def.owner, "treasury", None).unwrap();
+        assert_eq!(qy_timelock(&app, &def, "treasury").unwrap(), None);
+        assert_eq!(qy_pending_timelock(&app, &def, "treasury").unwrap(), None);
+        update_variable(&mut app, &def, &def.owner, "treasury", &treasury).unwrap();
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn multisig() {
+    let (mut app, def) = startup();
+
+    let owner_2 = app.generate_addr("owner_2");
+    let owner_3 = app.generate_addr("owner_3");
+    let random_addr = app.generate_addr("random_addr");
+
+    let var_1 = Variable::U64(1);
+
+    // --- Threshold validation ---
+    {
+        update_threshold(&mut app, &def, &def.owner, 0).unwrap_err_contains("Invalid 0 threshold");
+        update_threshold(&mut app, &def, &def.owner, 2).unwrap_err_contains("Greater than the number of owners");
+        update_owners(&mut app, &def, &def.owner, Some(&[&owner_2, &owner_3]), None).unwrap();
+        accept_ownership(&mut app, &def, &owner_2).unwrap();
+        accept_ownership(&mut app, &def, &owner_3).unwrap();
+        update_threshold(&mut app, &def, &def.owner, 2).unwrap();
+        assert_eq!(qy_config(&app, &def).unwrap().threshold, 2);
+    }
+
+    // --- Owner action becomes a proposal ---
+    let proposal_id = {
+        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
+        qy_variable(&app, &def, "var_1").unwrap_err_contains("variable not found");
+
+        let proposals = qy_proposals(&app, &def).unwrap();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].1.approvals, vec![def.owner.clone()]);
+        proposals[0].0
+    };
+
+    // --- Vote and execute ---
+    {
+        execute_proposal(&mut app, &def, &random_addr, proposal_id).unwrap_err_contains("has 1 approvals, needed 2");
+        vote(&mut app, &def, &def.owner, proposal_id).unwrap_err_contains("alredy voted");
+        vote(&mut app, &def, &random_addr, proposal_id).unwrap_err_contains("not an owner");
+        vote(&mut app, &def, &owner_2, proposal_id).unwrap();
+        close_proposal(&mut app, &def, &random_addr, proposal_id).unwrap_err_contains("is not expired");
+        execute_proposal(&mut app, &def, &random_addr, proposal_id).unwrap();
+        assert_eq!(qy_variable(&app, &def, "var_1").unwrap(), var_1);
+        assert_eq!(qy_proposals(&app, &def).unwrap(), vec![]);
+    }
+
+    // --- Invalid proposal fails on execution ---
+    let invalid_proposal_id = {
+        update_threshold(&mut app, &def, &owner_2, 4).unwrap();
+        let proposal_id = qy_proposals(&app, &def).unwrap()[0].0;
+        vote(&mut app, &def, &owner_3, proposal_id).unwrap();
+        execute_proposal(&mut app, &def, &random_addr, proposal_id).unwrap_err_contains("Greater than the number of owners");
+        proposal_id
+    };
+
+    // --- Expired proposal ---
+    {
+        remove_variable(&mut app, &def, &def.owner, "var_1").unwrap();
+        let proposal_id = qy_proposals(&app, &def).unwrap()[1].0;
+        app.increase_time(7 * 24 * 60 * 60);
+        vote(&mut app, &def, &owner_2, proposal_id).unwrap_err_contains("is expired");
+        execute_proposal(&mut app, &def, &random_addr, proposal_id).unwrap_err_contains("is expired");
+        close_proposal(&mut app, &def, &random_addr, proposal_id).unwrap();
+        close_proposal(&mut app, &def, &random_addr, invalid_proposal_id).unwrap();
+        assert_eq!(qy_proposals(&app, &def).unwrap(), vec![]);
+        assert_eq!(qy_variable(&app, &def, "var_1").unwrap(), var_1);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn schema() {
+    let (mut app, def) = startup();
+
+    let writer = app.generate_addr("writer");
+
+    let fee_schema = VariableSchema::Decimal { min: Some("0.01".into_decimal()), max: Some("0.5".into_decimal()) };
+    let name_schema = VariableSchema::String { max_len: Some(8), charset: Some("a-z_".to_string()) };
+
+    // --- Set schemas ---
+    {
+        set_schema(&mut app, &def, &def.owner, "fee_rate", Some(VariableSchema::Uint128 { min: Some(10u128.into()), max: Some(1u128.into()) })).unwrap_err_contains("min greater than max");
+        set_schema(&mut app, &def, &def.owner, "fee_rate", Some(fee_schema.clone())).unwrap();
+        set_schema(&mut app, &def, &def.owner, "name", Some(name_schema.clone())).unwrap();
+
+        grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "").unwrap();
+        set_schema(&mut app, &def, &writer, "fee_rate", None).unwrap_err_contains("not an owner");
+
+        assert_eq!(qy_schema(&app, &def, "fee_rate").unwrap(), Some(fee_schema));
+    }
+
+    // --- Schema enforced on register and update ---
+    {
+        register_variable(&mut app, &def, &def.owner, "fee_rate", &Variable::String("0.1".to_string())).unwrap_err_contains("Variable is not Decimal");
+        register_variable(&mut app, &def, &def.owner, "fee_rate", &Variable::Decimal("0.6".into_decimal())).unwrap_err_contains("greater than max");
+        register_variable(&mut app, &def, &def.owner, "fee_rate", &Variable::Decimal("0.1".into_decimal())).unwrap();
+        update_variable(&mut app, &def, &writer, "fee_rate", &Variable::Decimal("0.001".into_decimal())).unwrap_err_contains("lower than min");
+        update_variable(&mut app, &def, &writer, "fee_rate", &Variable::Decimal("0.2".into_decimal())).unwrap();
+
+        register_variable(&mut app, &def, &def.owner, "name", &Variable::String("too_long_name".to_string())).unwrap_err_contains("greater than max length");
+        register_variable(&mut app, &def, &def.owner, "name", &Variable::String("Name".to_string())).unwrap_err_contains("Char 'N' not allowed");
+        register_variable(&mut app, &def, &def.owner, "name", &Variable::String("my_name".to_string())).unwrap();
+    }
+
+    // --- Schema must match the current value ---
+    {
+        set_schema(&mut app, &def, &def.owner, "name", Some(VariableSchema::Addr {})).unwrap_err_contains("Schema violation on key name");
+        set_schema(&mut app, &def, &def.owner, "name", None).unwrap();
+        update_variable(&mut app, &def, &def.owner, "name", &Variable::U64(1)).unwrap();
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn namespaces() {
+    let (mut app, def) = startup();
+
+    let mars_owner = app.generate_addr("mars_owner");
+    let mars_writer = app.generate_addr("mars_writer");
+    let new_owner = app.generate_addr("new_owner");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    // --- Create namespace ---
+    {
+        create_namespace(&mut app, &def, &mars_owner, "mars", &[&mars_owner]).unwrap_err_contains("is not an owner");
+        create_namespace(&mut app, &def, &def.owner, DEFAULT_NAMESPACE, &[&mars_owner]).unwrap_err_contains("alredy exists");
+        create_namespace(&mut app, &def, &def.owner, "mars", &[&mars_owner]).unwrap();
+        create_namespace(&mut app, &def, &def.owner, "mars", &[&mars_owner]).unwrap_err_contains("alredy exists");
+
+        assert_eq!(qy_namespaces(&app, &def).unwrap(), vec![("mars".to_string(), Namespace { owners: vec![mars_owner.clone()] })]);
+    }
+
+    // --- Same key in different namespaces ---
+    {
+        register_variable_in(&mut app, &def, &def.owner, Some("venus"), "fee", &var_1).unwrap_err_contains("Namespace not found");
+        register_variable_in(&mut app, &def, &mars_owner, Some("mars"), "fee", &var_1).unwrap();
+        register_variable_in(&mut app, &def, &mars_owner, None, "fee", &var_1).unwrap_err_contains("not an owner");
+        register_variable(&mut app, &def, &def.owner, "fee", &var_2).unwrap();
+
+        assert_eq!(qy_variable_in(&app, &def, Some("mars"), "fee").unwrap(), var_1);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
+        assert_eq!(qy_all_variables_in(&app, &def, Some("mars"), None, None).unwrap(), vec![("fee".to_string(), var_1.clone())]);
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![("fee".to_string(), var_2.clone())]);
+    }
+
+    // --- Namespace owners manage roles and owners of their namespace ---
+    {
+        grant_role_in(&mut app, &def, &mars_owner, &mars_writer, Role::Writer, Some("mars"), "price.").unwrap();
+        grant_role_in(&mut app, &def, &mars_owner, &mars_writer, Role::Writer, None, "price.").unwrap_err_contains("not an owner");
+        register_variable_in(&mut app, &def, &mars_writer, Some("mars"), "price.atom", &var_1).unwrap();
+        register_variable(&mut app, &def, &mars_writer, "price.atom", &var_1).unwrap_err_contains("not an owner");
+
+        update_namespace_owners(&mut app, &def, &mars_writer, "mars", Some(&[&new_owner]), None).unwrap_err_contains("Unauthorized");
+        update_namespace_owners(&mut app, &def, &mars_owner, "mars", Some(&[&new_owner]), Some(&[&mars_owner])).unwrap();
+        register_variable_in(&mut app, &def, &mars_owner, Some("mars"), "other", &var_1).unwrap_err_contains("not an owner");
+        register_variable_in(&mut app, &def, &new_owner, Some("mars"), "other", &var_1).unwrap();
+    }
+
+    // --- Delete namespace ---
+    {
+        delete_namespace(&mut app, &def, &new_owner, "mars").unwrap_err_contains("is not an owner");
+        delete_namespace(&mut app, &def, &def.owner, "mars").unwrap_err_contains("still contains variables");
+
+        for key in ["fee", "price.atom", "other"] {
+            remove_variable_in(&mut app, &def, &new_owner, Some("mars"), key).unwrap();
+        }
+
+        delete_namespace(&mut app, &def, &def.owner, "mars").unwrap();
+        assert_eq!(qy_namespaces(&app, &def).unwrap(), vec![]);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
+        assert_eq!(qy_roles(&app, &def, &mars_writer).unwrap(), vec![]);
+
+        // Grants of the deleted namespace are not inherited
+        create_namespace(&mut app, &def, &def.owner, "mars", &[&mars_owner]).unwrap();
+        register_variable_in(&mut app, &def, &mars_writer, Some("mars"), "price.atom", &var_1).unwrap_err_contains("not an owner");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn prefix_and_range() {
+    let (mut app, def) = startup();
+
+    let keys = ["market.atom", "market.btc", "market.eth", "marketing", "oracle.atom"];
+
+    for (index, key) in keys.iter().enumerate() {
+        register_variable(&mut app, &def, &def.owner, key, &Variable::U64(index as u64)).unwrap();
+    }
+
+    let keys_of = |items: Vec<(String, Variable)>| items.into_iter().map(|(key, _)| key).collect::<Vec<String>>();
+
+    // --- By prefix ---
+    {
+        assert_eq!(keys_of(qy_variables_by_prefix(&app, &def, "market.", None, StorageOrder::Ascending).unwrap()), vec!["market.atom", "market.btc"]);
+        assert_eq!(keys_of(qy_variables_by_prefix(&app, &def, "market.", Some("market.btc"), StorageOrder::Ascending).unwrap()), vec!["market.eth"]);
+        assert_eq!(keys_of(qy_variables_by_prefix(&app, &def, "market.", None, StorageOrder::Descending).unwrap()), vec!["market.eth", "market.btc"]);
+        assert_eq!(keys_of(qy_variables_by_prefix(&app, &def, "market.", Some("market.btc"), StorageOrder::Descending).unwrap()), vec!["market.atom"]);
+        assert_eq!(keys_of(qy_variables_by_prefix(&app, &def, "none.", None, StorageOrder::Ascending).unwrap()), Vec::<String>::new());
+    }
+
+    // --- By range ---
+    {
+        assert_eq!(keys_of(qy_variables_in_range(&app, &def, Some("market.btc"), Some("oracle.atom"), StorageOrder::Ascending).unwrap()), vec!["market.btc", "market.eth", "marketing"]);
+        assert_eq!(keys_of(qy_variables_in_range(&app, &def, Some("market.btc"), Some("oracle.atom"), StorageOrder::Descending).unwrap()), vec!["marketing", "market.eth", "market.btc"]);
+        assert_eq!(keys_of(qy_variables_in_range(&app, &def, Some("marketing"), None, StorageOrder::Ascending).unwrap()), vec!["marketing", "oracle.atom"]);
+        assert_eq!(keys_of(qy_variables_in_range(&app, &def, None, Some("market.btc"), StorageOrder::Ascending).unwrap()), vec!["market.atom"]);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn hooks() {
+    let (mut app, def) = startup();
+
+    let consumer_1 = deploy_consumer(&mut app, &def, "consumer_1");
+    let consumer_2 = deploy_consumer(&mut app, &def, "consumer_2");
+    let random_addr = app.generate_addr("random_addr");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    // --- Subscribe ---
+    {
+        subscribe(&mut app, &def, &random_addr, "", &consumer_1, HookFailurePolicy::Ignore).unwrap_err_contains("not an owner");
+        subscribe(&mut app, &def, &def.owner, "", &consumer_1, HookFailurePolicy::Ignore).unwrap();
+        subscribe(&mut app, &def, &def.owner, "market.", &consumer_2, HookFailurePolicy::Revert).unwrap();
+
+        assert_eq!(qy_subscriptions(&app, &def).unwrap(), vec![
+            Subscription { namespace: DEFAULT_NAMESPACE.to_string(), prefix: "".to_string(), contract: consumer_1.clone(), on_error: HookFailurePolicy::Ignore },
+            Subscription { namespace: DEFAULT_NAMESPACE.to_string(), prefix: "market.".to_string(), contract: consumer_2.clone(), on_error: HookFailurePolicy::Revert },
+        ]);
+    }
+
+    // --- Hooks sent on update and remove ---
+    {
+        register_variable(&mut app, &def, &def.owner, "market.fee", &var_1).unwrap();
+        register_variable(&mut app, &def, &def.owner, "other", &var_1).unwrap();
+        assert_eq!(qy_consumer_hooks(&app, &consumer_1).unwrap(), vec![]);
+
+        update_variable(&mut app, &def, &def.owner, "market.fee", &var_2).unwrap();
+        remove_variable(&mut app, &def, &def.owner, "other").unwrap();
+
+        let update_hook = VariableChangedHook { namespace: DEFAULT_NAMESPACE.to_string(), key: "market.fee".to_string(), old: Some(var_1.clone()), new: Some(var_2.clone()) };
+        let remove_hook = VariableChangedHook { namespace: DEFAULT_NAMESPACE.to_string(), key: "other".to_string(), old: Some(var_1.clone()), new: None };
+
+        assert_eq!(qy_consumer_hooks(&app, &consumer_1).unwrap(), vec![update_hook.clone(), remove_hook]);
+        assert_eq!(qy_consumer_hooks(&app, &consumer_2).unwrap(), vec![update_hook]);
+    }
+
+    // --- Failure policy ---
+    {
+        register_variable(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &var_1).unwrap();
+
+        let res = update_variable(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &var_2).unwrap();
+        assert!(res.events.iter().any(|event| event.attributes.iter().any(|attr| attr.value == "hook_failed")));
+        assert_eq!(qy_variable(&app, &def, mock_consumer::FAILING_KEY).unwrap(), var_2);
+
+        subscribe(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &consumer_2, HookFailurePolicy::Revert).unwrap();
+        update_variable(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &var_1).unwrap_err_contains("consumer failed");
+        assert_eq!(qy_variable(&app, &def, mock_consumer::FAILING_KEY).unwrap(), var_2);
+    }
+
+    // --- Unsubscribe ---
+    {
+        unsubscribe(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &consumer_2).unwrap();
+        unsubscribe(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &consumer_2).unwrap_err_contains("is not subscribed");
+        update_variable(&mut app, &def, &def.owner, mock_consumer::FAILING_KEY, &var_1).unwrap();
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn variable_kinds() {
+    let (mut app, def) = startup();
+
+    let variables = vec![
+        ("bool", Variable::Bool(true)),
+        ("int128", Variable::Int128(Int128::new(-10))),
+        ("i64", Variable::I64(-10)),
+        ("signed_decimal", Variable::SignedDecimal(SignedDecimal::from_str("-1.5").unwrap())),
+        ("uint256", Variable::Uint256(Uint256::from(10u128))),
+        ("decimal256", Variable::Decimal256(Decimal256::from_str("1.5").unwrap())),
+        ("coin", Variable::Coin(coin(1, "ibc/ABC"))),
+        ("coins", Variable::Coins(vec![coin(1, "uatom"), coin(2, "uosmo")])),
+        ("timestamp", Variable::Timestamp(Timestamp::from_seconds(10))),
+        ("duration", Variable::Duration(Duration::Height(10))),
+        ("list", Variable::List(vec![Variable::U64(1), Variable::U64(2)])),
+        ("map", Variable::Map(BTreeMap::from([("a".to_string(), Variable::Addr(def.owner.clone()))]))),
+    ];
+
+    // --- Register and query every kind ---
+    {
+        for (key, variable) in &variables {
+            register_variable(&mut app, &def, &def.owner, key, variable).unwrap();
+            assert_eq!(qy_variable(&app, &def, key).unwrap(), variable.clone());
+        }
+    }
+
+    // --- Validation ---
+    {
+        register_variable(&mut app, &def, &def.owner, "invalid", &Variable::Coin(coin(1, "1atom"))).unwrap_err_contains("Invalid denom: 1atom");
+        register_variable(&mut app, &def, &def.owner, "invalid", &Variable::Coins(vec![coin(1, "uatom"), coin(2, "uatom")])).unwrap_err_contains("Duplicate denom uatom");
+        register_variable(&mut app, &def, &def.owner, "invalid", &Variable::List(vec![Variable::U64(1), Variable::Bool(true)])).unwrap_err_contains("Mixed kinds not allowed, found U64 and Bool");
+        register_variable(&mut app, &def, &def.owner, "invalid", &Variable::Map(BTreeMap::from([("a".to_string(), Variable::Addr(Addr::unchecked("invalid")))]))).unwrap_err_contains("Invalid input");
+    }
+
+    // --- Schema ---
+    {
+        set_schema(&mut app, &def, &def.owner, "coin", Some(VariableSchema::Coin { denom: Some("uatom".to_string()) })).unwrap_err_contains("Denom ibc/ABC not allowed");
+        set_schema(&mut app, &def, &def.owner, "i64", Some(VariableSchema::I64 { min: Some(-20), max: Some(0) })).unwrap();
+        update_variable(&mut app, &def, &def.owner, "i64", &Variable::I64(1)).unwrap_err_contains("greater than max");
+        set_schema(&mut app, &def, &def.owner, "list", Some(VariableSchema::List { max_len: Some(2) })).unwrap();
+        update_variable(&mut app, &def, &def.owner, "list", &Variable::List(vec![Variable::U64(1); 3])).unwrap_err_contains("greater than max length");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn typed_access() {
+    let (mut app, def) = startup();
+
+    register_variable(&mut app, &def, &def.owner, "owner", &def.owner.clone().into()).unwrap();
+    register_variable(&mut app, &def, &def.owner, "fee", &Decimal::percent(5).into()).unwrap();
+    register_variable(&mut app, &def, &def.owner, "coins", &vec![coin(1, "uatom")].into()).unwrap();
+
+    assert_eq!(get_variable::<Addr>(&app.wrap(), None, "owner", &def.vm_addr).unwrap(), def.owner);
+    assert_eq!(get_variable::<Decimal>(&app.wrap(), None, "fee", &def.vm_addr).unwrap(), Decimal::percent(5));
+    assert_eq!(get_variable::<Vec<Coin>>(&app.wrap(), None, "coins", &def.vm_addr).unwrap(), vec![coin(1, "uatom")]);
+
+    assert_eq!(get_variable::<u64>(&app.wrap(), None, "fee", &def.vm_addr).unwrap_err(), VariableError::TypeMismatch { expected: "U64", actual: "Decimal" });
+    assert!(matches!(get_variable::<u64>(&app.wrap(), None, "missing", &def.vm_addr).unwrap_err(), VariableError::Std(_)));
+
+    assert_eq!(bool::try_from(Variable::Bool(true)), Ok(true));
+    assert_eq!(String::try_from(Variable::U64(1)), Err(VariableError::TypeMismatch { expected: "String", actual: "U64" }));
+    assert_eq!(Variable::from("value"), Variable::String("value".to_string()));
+}
+
+#[test]
+#[rustfmt::skip]
+fn derive_from_variable_manager() {
+    let (mut app, def) = startup();
+
+    fn default_max_items() -> u64 {
+        10
+    }
+
+    #[derive(FromVariableManager, Debug, PartialEq)]
+    struct ProtocolConfig {
+        #[vm(key = "protocol.oracle")]
+        oracle: Addr,
+        fee: Decimal,
+        #[vm(default)]
+        enabled: bool,
+        #[vm(key = "protocol.max_items", default = "default_max_items")]
+        max_items: u64,
+    }
+
+    #[derive(FromVariableManager, Debug, PartialEq)]
+    #[vm(namespace = "mars")]
+    struct MarsConfig {
+        fee: Decimal,
+    }
+
+    assert_eq!(ProtocolConfig::keys(), vec!["protocol.oracle", "fee", "enabled", "protocol.max_items"]);
+
+    // --- Missing and mismatched fields ---
+    {
+        assert_eq!(variable_manager_load::<ProtocolConfig>(&app.wrap(), &def.vm_addr).unwrap_err().to_string(), "Field oracle (key protocol.oracle): Variable not found: protocol.oracle");
+
+        register_variable(&mut app, &def, &def.owner, "protocol.oracle", &def.owner.clone().into()).unwrap();
+        register_variable(&mut app, &def, &def.owner, "fee", &Variable::U64(1)).unwrap();
+
+        assert_eq!(variable_manager_load::<ProtocolConfig>(&app.wrap(), &def.vm_addr).unwrap_err().to_string(), "Field fee (key fee): Variable type mismatch: expected Decimal, found U64");
+    }
+
+    // --- Load with defaults ---
+    {
+        update_variable(&mut app, &def, &def.owner, "fee", &Decimal::percent(1).into()).unwrap();
+
+        assert_eq!(variable_manager_load::<ProtocolConfig>(&app.wrap(), &def.vm_addr).unwrap(), ProtocolConfig { oracle: def.owner.clone(), fee: Decimal::percent(1), enabled: false, max_items: 10 });
+
+        register_variable(&mut app, &def, &def.owner, "enabled", &true.into()).unwrap();
+        register_variable(&mut app, &def, &def.owner, "protocol.max_items", &5u64.into()).unwrap();
+
+        assert_eq!(variable_manager_load::<ProtocolConfig>(&app.wrap(), &def.vm_addr).unwrap(), ProtocolConfig { oracle: def.owner.clone(), fee: Decimal::percent(1), enabled: true, max_items: 5 });
+    }
+
+    // --- Namespace ---
+    {
+        create_namespace(&mut app, &def, &def.owner, "mars", &[&def.owner]).unwrap();
+        register_variable_in(&mut app, &def, &def.owner, Some("mars"), "fee", &Decimal::percent(2).into()).unwrap();
+
+        assert_eq!(variable_manager_load::<MarsConfig>(&app.wrap(), &def.vm_addr).unwrap(), MarsConfig { fee: Decimal::percent(2) });
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn batch_operations() {
+    let (mut app, def) = startup();
+
+    let writer = app.generate_addr("writer");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    let remove = |key: &str| Operation::Remove(RemoveVariableMsg { namespace: None, key: key.to_string() });
+
+    register_variables(&mut app, &def, &def.owner, vec![("old.fee", &var_1), ("rate", &var_1)]).unwrap();
+
+    // --- Rename a key and upsert in one transaction ---
+    {
+        let res = batch(&mut app, &def, &def.owner, vec![
+            Operation::Register(RegisterVariableMsg::new("new.fee".to_string(), var_1.clone())),
+            remove("old.fee"),
+            Operation::Upsert(RegisterVariableMsg::new("rate".to_string(), var_2.clone())),
+            Operation::Upsert(RegisterVariableMsg::new("limit".to_string(), var_2.clone())),
+        ]).unwrap();
+
+        let operations: Vec<String> = res.events.iter().flat_map(|event| event.attributes.iter()).filter(|attr| attr.key == "operation").map(|attr| attr.value.clone()).collect();
+        assert_eq!(operations, vec!["register_variable", "remove_variable", "update_variable", "register_variable"]);
+
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![
+            ("limit".to_string(), var_2.clone()),
+            ("new.fee".to_string(), var_1.clone()),
+            ("rate".to_string(), var_2.clone()),
+        ]);
+    }
+
+    // --- Atomicity ---
+    {
+        batch(&mut app, &def, &def.owner, vec![
+            Operation::Register(RegisterVariableMsg::new("other".to_string(), var_1.clone())),
+            Operation::Update(RegisterVariableMsg::new("missing".to_string(), var_1.clone())),
+        ]).unwrap_err_contains("Key not found: missing");
+
+        qy_variable(&app, &def, "other").unwrap_err_contains("not found");
+    }
+
+    // --- Roles checked on every operation ---
+    {
+        grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "new.").unwrap();
+        batch(&mut app, &def, &writer, vec![remove("new.fee"), remove("rate")]).unwrap_err_contains("no role allowing to modify rate");
+        batch(&mut app, &def, &writer, vec![remove("new.fee")]).unwrap();
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn upsert_and_compare_and_swap() {
+    let (mut app, def) = startup();
+
+    let writer = app.generate_addr("writer");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+    let var_3 = Variable::U64(3);
+
+    // --- Upsert ---
+    {
+        upsert(&mut app, &def, &def.owner, "fee", &var_1).unwrap();
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_1);
+
+        upsert(&mut app, &def, &def.owner, "fee", &var_2).unwrap();
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
+    }
+
+    // --- Compare and swap ---
+    {
+        compare_and_swap(&mut app, &def, &def.owner, "fee", Some(&var_1), &var_3).unwrap_err_contains("Compare and swap failed on key fee: expected u64: 1, found u64: 2");
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
+
+        let res = compare_and_swap(&mut app, &def, &def.owner, "fee", Some(&var_2), &var_3).unwrap();
+        assert!(res.events.iter().flat_map(|event| event.attributes.iter()).any(|attr| attr.key == "action" && attr.value == "compare_and_swap"));
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_3);
+
+        // Expected `None` only succeeds if the key is not registered
+        compare_and_swap(&mut app, &def, &def.owner, "fee", None, &var_1).unwrap_err_contains("expected none, found u64: 3");
+        compare_and_swap(&mut app, &def, &def.owner, "rate", None, &var_1).unwrap();
+        assert_eq!(qy_variable(&app, &def, "rate").unwrap(), var_1);
+    }
+
+    // --- Roles ---
+    {
+        upsert(&mut app, &def, &writer, "fee", &var_1).unwrap_err_contains("no role allowing to modify fee");
+        compare_and_swap(&mut app, &def, &writer, "fee", Some(&var_3), &var_1).unwrap_err_contains("no role allowing to modify fee");
+
+        grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "fee").unwrap();
+        compare_and_swap(&mut app, &def, &writer, "fee", Some(&var_3), &var_1).unwrap();
+        upsert(&mut app, &def, &writer, "fee", &var_2).unwrap();
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn remove_many() {
+    let (mut app, def) = startup();
+
+    let writer = app.generate_addr("writer");
+
+    let var_1 = Variable::U64(1);
+
+    let attribute = |res: &AppResponse, key: &str| -> Vec<String> {
+        res.events.iter().filter(|event| event.ty == "wasm").flat_map(|event| event.attributes.iter()).filter(|attr| attr.key == key).map(|attr| attr.value.clone()).collect()
+    };
+
+    register_variables(&mut app, &def, &def.owner, vec![("a", &var_1), ("b", &var_1), ("c", &var_1), ("pool.1", &var_1), ("pool.2", &var_1), ("pool.3", &var_1), ("pools", &var_1)]).unwrap();
+
+    // --- Remove a list of keys ---
+    {
+        remove_variables(&mut app, &def, &def.owner, vec!["a", "missing"]).unwrap_err_contains("Key not found: missing");
+        qy_variable(&app, &def, "a").unwrap();
+
+        let res = remove_variables(&mut app, &def, &def.owner, vec!["a", "b"]).unwrap();
+        assert_eq!(attribute(&res, "key"), vec!["a", "b"]);
+        assert_eq!(attribute(&res, "value"), vec!["u64: 1", "u64: 1"]);
+
+        qy_variable(&app, &def, "a").unwrap_err_contains("not found");
+        qy_variable(&app, &def, "b").unwrap_err_contains("not found");
+    }
+
+    // --- Remove by prefix with paging ---
+    {
+        let res = remove_by_prefix(&mut app, &def, &def.owner, "pool.", Some(2)).unwrap();
+        assert_eq!(attribute(&res, "key"), vec!["pool.1", "pool.2"]);
+        assert_eq!(attribute(&res, "has_more"), vec!["true"]);
+
+        let res = remove_by_prefix(&mut app, &def, &def.owner, "pool.", Some(2)).unwrap();
+        assert_eq!(attribute(&res, "key"), vec!["pool.3"]);
+        assert_eq!(attribute(&res, "has_more"), vec!["false"]);
+
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![
+            ("c".to_string(), var_1.clone()),
+            ("pools".to_string(), var_1.clone()),
+        ]);
+    }
+
+    // --- Roles ---
+    {
+        grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "pool").unwrap();
+        remove_variables(&mut app, &def, &writer, vec!["pools", "c"]).unwrap_err_contains("no role allowing to modify c");
+        remove_by_prefix(&mut app, &def, &writer, "", None).unwrap_err_contains("no role allowing to modify");
+        remove_by_prefix(&mut app, &def, &writer, "pool", None).unwrap();
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![("c".to_string(), var_1.clone())]);
+    }
+
+    // --- Locked keys are skipped ---
+    {
+        register_variables(&mut app, &def, &def.owner, vec![("lock.1", &var_1), ("lock.2", &var_1), ("lock.3", &var_1), ("lock.4", &var_1)]).unwrap();
+        set_timelock(&mut app, &def, &def.owner, "lock.1", Some(100)).unwrap();
+        freeze_variable(&mut app, &def, &def.owner, "lock.2", None).unwrap();
+
+        // Skipped keys count toward the limit
+        let res = remove_by_prefix(&mut app, &def, &def.owner, "lock.", Some(2)).unwrap();
+        assert_eq!(attribute(&res, "key"), Vec::<String>::new());
+        assert_eq!(attribute(&res, "skipped"), vec!["lock.1", "lock.2"]);
+        assert_eq!(attribute(&res, "has_more"), vec!["true"]);
+        assert_eq!(attribute(&res, "last_key"), vec!["lock.2"]);
+
+        let res = remove_by_prefix_after(&mut app, &def, &def.owner, "lock.", Some("lock.2"), Some(1)).unwrap();
+        assert_eq!(attribute(&res, "key"), vec!["lock.3"]);
+        assert_eq!(attribute(&res, "last_key"), vec!["lock.3"]);
+
+        let res = remove_by_prefix_after(&mut app, &def, &def.owner, "lock.", Some("lock.3"), Some(2)).unwrap();
+        assert_eq!(attribute(&res, "key"), vec!["lock.4"]);
+        assert_eq!(attribute(&res, "has_more"), vec!["false"]);
+        assert_eq!(attribute(&res, "last_key"), Vec::<String>::new());
+
+        let keys = qy_all_variables(&app, &def, None, None).unwrap().into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["c", "lock.1", "lock.2"]);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn migration() {
+    let (mut app, def) = startup();
+
+    let owner_2 = app.generate_addr("owner_2");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::Addr(def.owner.clone());
+
+    let code_id = store_vm_code(&mut app);
+
+    let attribute = |res: &AppResponse, key: &str| -> Vec<String> {
+        res.events.iter().flat_map(|event| event.attributes.iter()).filter(|attr| attr.key == key).map(|attr| attr.value.clone()).collect()
+    };
+
+    // --- Migrate from the 1.0.4 layout ---
+    {
+        let legacy = Def { owner: def.owner.clone(), vm_addr: deploy_legacy(&mut app, &def.owner, vec![("fee", &var_1), ("treasury", &var_2)], None) };
+
+        migrate(&mut app, &legacy, &def.owner, code_id, MigrateMsg::default()).unwrap_err_contains("must be migrated from the 1.0.4 layout");
+
+        let res = migrate(&mut app, &legacy, &def.owner, code_id, MigrateMsg {
+            migrate_storage: Some(true),
+            update_owners: Some(UpdateOwnerMsg { add: Some(vec![owner_2.to_string()]), remove: None, threshold: None }),
+        }).unwrap();
+        assert_eq!(attribute(&res, "migrated_variables"), vec!["2"]);
+
+        accept_ownership(&mut app, &legacy, &owner_2).unwrap();
+
+        assert_eq!(qy_config(&app, &legacy).unwrap(), Config { owners: vec![def.owner.clone(), owner_2.clone()], threshold: 1, guardian: None, paused: false, max_reference_depth: DEFAULT_MAX_REFERENCE_DEPTH });
+        assert_eq!(qy_all_variables(&app, &legacy, None, None).unwrap(), vec![
+            ("fee".to_string(), var_1.clone()),
+            ("treasury".to_string(), var_2.clone()),
+        ]);
+
+        let version = cw2::query_contract_info(&app.wrap(), &legacy.vm_addr).unwrap();
+        assert_eq!(version.contract, "crates.io:variable-manager");
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        // The migrated contract is fully functional
+        update_variable(&mut app, &legacy, &owner_2, "fee", &var_2).unwrap();
+        register_variable(&mut app, &legacy, &owner_2, "rate", &var_1).unwrap();
+        assert_eq!(qy_variable(&app, &legacy, "fee").unwrap(), var_2);
+
+        migrate(&mut app, &legacy, &def.owner, code_id, MigrateMsg { migrate_storage: Some(true), update_owners: None }).unwrap_err_contains("Storage already uses the current layout");
+    }
+
+    // --- Migrate the current layout ---
+    {
+        register_variable(&mut app, &def, &def.owner, "fee", &var_1).unwrap();
+
+        let res = migrate(&mut app, &def, &def.owner, code_id, MigrateMsg::default()).unwrap();
+        assert_eq!(attribute(&res, "from_version"), vec![env!("CARGO_PKG_VERSION")]);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_1);
+
+        let invalid_owners = MigrateMsg { migrate_storage: None, update_owners: Some(UpdateOwnerMsg { add: None, remove: Some(vec![def.owner.to_string()]), threshold: None }) };
+        migrate(&mut app, &def, &def.owner, code_id, invalid_owners).unwrap_err();
+    }
+
+    // --- Version checks ---
+    {
+        let other = Def { owner: def.owner.clone(), vm_addr: deploy_legacy(&mut app, &def.owner, vec![], Some(("crates.io:other", "1.0.0"))) };
+        migrate(&mut app, &other, &def.owner, code_id, MigrateMsg::default()).unwrap_err_contains("Cannot migrate from contract crates.io:other");
+
+        let newer = Def { owner: def.owner.clone(), vm_addr: deploy_legacy(&mut app, &def.owner, vec![], Some(("crates.io:variable-manager", "99.0.0"))) };
+        migrate(&mut app, &newer, &def.owner, code_id, MigrateMsg::default()).unwrap_err_contains("Cannot migrate from version 99.0.0 to older version");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn expiry() {
+    let (mut app, def) = startup();
+
+    let random_addr = app.generate_addr("random_addr");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    let height = app.block_info().height;
+    let time = app.block_info().time;
+
+    let expiring = |key: &str, expires_at: Expiration| ExpiringVariable { namespace: DEFAULT_NAMESPACE.to_string(), key: key.to_string(), expires_at };
+
+    // --- Register with expiration ---
+    {
+        register_expiring_variable(&mut app, &def, &def.owner, "past", &var_1, Expiration::AtHeight(height)).unwrap_err_contains("Expiration of key past already reached");
+
+        register_expiring_variable(&mut app, &def, &def.owner, "override", &var_1, Expiration::AtHeight(height + 2)).unwrap();
+        register_expiring_variable(&mut app, &def, &def.owner, "promo", &var_1, Expiration::AtTime(time.plus_seconds(100))).unwrap();
+        register_expiring_variable(&mut app, &def, &def.owner, "promo_2", &var_1, Expiration::AtTime(time.plus_seconds(200))).unwrap();
+        register_expiring_variable(&mut app, &def, &def.owner, "promo_3", &var_1, Expiration::AtTime(time.plus_seconds(300))).unwrap();
+        register_variable(&mut app, &def, &def.owner, "fee", &var_1).unwrap();
+
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Height(10), None).unwrap(), vec![expiring("override", Expiration::AtHeight(height + 2))]);
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Time(100), None).unwrap(), vec![expiring("promo", Expiration::AtTime(time.plus_seconds(100)))]);
+
+        let page = qy_expiring_variables(&app, &def, Duration::Time(1000), None).unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Time(1000), page.last().cloned()).unwrap(), vec![expiring("promo_3", Expiration::AtTime(time.plus_seconds(300)))]);
+
+        // Windows past the end of time don't overflow
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Time(u64::MAX), None).unwrap(), page);
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Height(u64::MAX), None).unwrap(), vec![expiring("override", Expiration::AtHeight(height + 2))]);
+    }
+
+    // --- Update keeps the expiration unless a new one is set ---
+    {
+        update_variable(&mut app, &def, &def.owner, "promo_3", &var_2).unwrap();
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Time(1000), Some(expiring("promo_2", Expiration::AtTime(time.plus_seconds(200))))).unwrap().len(), 1);
+
+        app.execute_contract(def.owner.clone(), def.vm_addr.clone(), &ExecuteMsg::UpdateVariable(RegisterVariableMsg {
+            expires_at: Some(Expiration::Never {}),
+            ..RegisterVariableMsg::new("promo_3".to_string(), var_2.clone())
+        }), &[]).unwrap();
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Time(1000), Some(expiring("promo_2", Expiration::AtTime(time.plus_seconds(200))))).unwrap(), vec![]);
+    }
+
+    // --- Expired variables are treated as absent ---
+    {
+        app.update_block(next_block);
+        app.update_block(next_block);
+
+        qy_variable(&app, &def, "override").unwrap_err_contains("not found");
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![
+            ("fee".to_string(), var_1.clone()),
+            ("promo".to_string(), var_1.clone()),
+            ("promo_2".to_string(), var_1.clone()),
+            ("promo_3".to_string(), var_2.clone()),
+        ]);
+        update_variable(&mut app, &def, &def.owner, "override", &var_2).unwrap_err_contains("Key not found: override");
+
+        app.increase_time(150);
+        qy_variable(&app, &def, "promo").unwrap_err_contains("not found");
+        assert_eq!(qy_expiring_variables(&app, &def, Duration::Time(1000), None).unwrap(), vec![expiring("promo_2", Expiration::AtTime(time.plus_seconds(200)))]);
+    }
+
+    // --- Prune ---
+    {
+        let res = prune_expired(&mut app, &def, &random_addr, Some(1)).unwrap();
+        assert!(res.events.iter().flat_map(|event| event.attributes.iter()).any(|attr| attr.key == "key" && attr.value == "override"));
+
+        prune_expired(&mut app, &def, &random_addr, None).unwrap();
+        assert!(qy_variable_history(&app, &def, "promo", None, None).unwrap().iter().any(|(_, entry)| entry.action == VariableAction::Remove));
+
+        let res = prune_expired(&mut app, &def, &random_addr, None).unwrap();
+        assert!(!res.events.iter().flat_map(|event| event.attributes.iter()).any(|attr| attr.key == "key"));
+
+        // An expired key can be registered again
+        register_variable(&mut app, &def, &def.owner, "override", &var_2).unwrap();
+        assert_eq!(qy_variable(&app, &def, "override").unwrap(), var_2);
+    }
+
+    // --- Register over an expired key not pruned ---
+    {
+        let height = app.block_info().height;
+
+        register_expiring_variable(&mut app, &def, &def.owner, "stale", &var_1, Expiration::AtHeight(height + 1)).unwrap();
+        propose_update(&mut app, &def, &def.owner, "stale", &var_2).unwrap();
+        schedule_variable(&mut app, &def, &def.owner, "stale", &var_2, Scheduled::AtHeight(height + 10)).unwrap();
+        set_timelock(&mut app, &def, &def.owner, "stale", Some(100)).unwrap();
+
+        app.update_block(next_block);
+        register_variable(&mut app, &def, &def.owner, "stale", &var_1).unwrap();
+
+        assert_eq!(qy_pending_update(&app, &def, "stale").unwrap(), None);
+        assert_eq!(qy_timelock(&app, &def, "stale").unwrap(), None);
+        assert_eq!(qy_schedule(&app, &def, "stale").unwrap(), vec![]);
+        execute_pending(&mut app, &def, &random_addr, "stale").unwrap_err_contains("Pending update not found");
+        update_variable(&mut app, &def, &def.owner, "stale", &var_2).unwrap();
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn scheduled_values() {
+    let (mut app, def) = startup();
+
+    let random_addr = app.generate_addr("random_addr");
+
+    let fee_1 = Variable::Decimal(Decimal::from_str("0.01").unwrap());
+    let fee_2 = Variable::Decimal(Decimal::from_str("0.02").unwrap());
+    let fee_3 = Variable::Decimal(Decimal::from_str("0.03").unwrap());
+    let fee_4 = Variable::Decimal(Decimal::from_str("0.04").unwrap());
+
+    let height = app.block_info().height;
+    let time = app.block_info().time;
+
+    let scheduled = |value: &Variable, activates_at: u64| ScheduledValue { value: value.clone(), activates_at: Scheduled::AtHeight(activates_at) };
+
+    register_variable(&mut app, &def, &def.owner, "fee", &fee_1).unwrap();
+
+    // --- Schedule ---
+    {
+        schedule_variable(&mut app, &def, &def.owner, "missing", &fee_2, Scheduled::AtHeight(height + 1)).unwrap_err_contains("Key not found: missing");
+        schedule_variable(&mut app, &def, &random_addr, "fee", &fee_2, Scheduled::AtHeight(height + 1)).unwrap_err_contains("not an owner");
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_2, Scheduled::AtHeight(height)).unwrap_err_contains("already reached");
+
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_3, Scheduled::AtHeight(height + 2)).unwrap();
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_2, Scheduled::AtHeight(height + 1)).unwrap();
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_4, Scheduled::AtHeight(height + 3)).unwrap();
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_4, Scheduled::AtHeight(height + 3)).unwrap_err_contains("a value is already scheduled");
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_4, Scheduled::AtTime(time.plus_seconds(100))).unwrap_err_contains("heights and times can not be mixed");
+
+        assert_eq!(qy_schedule(&app, &def, "fee").unwrap(), vec![scheduled(&fee_2, height + 1), scheduled(&fee_3, height + 2), scheduled(&fee_4, height + 3)]);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee_1);
+    }
+
+    // --- Values activate at their height ---
+    {
+        app.update_block(next_block);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee_2);
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![("fee".to_string(), fee_2.clone())]);
+
+        // The activation is not a write
+        assert_eq!(qy_variable_at_height(&app, &def, "fee", height + 1).unwrap(), Some(fee_1.clone()));
+        assert_eq!(qy_variable_history(&app, &def, "fee", None, None).unwrap().len(), 1);
+
+        app.update_block(next_block);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee_3);
+        assert_eq!(qy_schedule(&app, &def, "fee").unwrap(), vec![scheduled(&fee_3, height + 2), scheduled(&fee_4, height + 3)]);
+
+        cancel_scheduled(&mut app, &def, &def.owner, "fee", Scheduled::AtHeight(height + 2)).unwrap_err_contains("No pending value of key fee");
+        cancel_scheduled(&mut app, &def, &def.owner, "fee", Scheduled::AtHeight(height + 3)).unwrap();
 
-    // --- OK  update variable ---
+        app.update_block(next_block);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee_3);
+    }
+
+    // --- Writes replace the active value and keep the pending ones ---
     {
-        register_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
-        var_1 = Variable::String("var_1_updated".to_string());
-        update_variable(&mut app, &def, &def.owner, "var_1", &var_1).unwrap();
+        schedule_variable(&mut app, &def, &def.owner, "fee", &fee_4, Scheduled::AtHeight(height + 5)).unwrap();
+
+        compare_and_swap(&mut app, &def, &def.owner, "fee", Some(&fee_3), &fee_1).unwrap();
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee_1);
+        assert_eq!(qy_schedule(&app, &def, "fee").unwrap(), vec![scheduled(&fee_4, height + 5)]);
+
+        app.update_block(next_block);
+        app.update_block(next_block);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee_4);
+
+        remove_variable(&mut app, &def, &def.owner, "fee").unwrap();
+        assert_eq!(qy_schedule(&app, &def, "fee").unwrap(), vec![]);
     }
+}
 
-    // --- Err update unexisting variable ---
+#[test]
+#[rustfmt::skip]
+fn metadata() {
+    let (mut app, def) = startup();
+
+    let writer = app.generate_addr("writer");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    let created = Modification { height: app.block_info().height, time: app.block_info().time, sender: def.owner.clone() };
+
+    register_variables(&mut app, &def, &def.owner, vec![("fee", &var_1), ("rate", &var_1), ("limit", &var_1)]).unwrap();
+
+    // --- Created on register ---
     {
-        update_variable(&mut app, &def, &def.owner, "var_10", &var_1).unwrap_err_contains("Key not found: var_10");
+        assert_eq!(qy_variable_with_meta(&app, &def, "fee").unwrap(), VariableWithMeta {
+            value: var_1.clone(),
+            metadata: Some(VariableMetadata { description: None, tags: vec![], created: created.clone(), updated: created.clone() }),
+        });
     }
 
-    // --- OK  update variables ---
+    // --- Set description and tags ---
     {
-        var_2 = Variable::Uint128(200u128.into());
-        var_3 = Variable::Binary(Binary(vec![4, 3, 2, 1]));
-        update_variables(&mut app, &def, &def.owner, vec![("var_2", &var_2), ("var_3", &var_3)]).unwrap(); 
+        set_metadata(&mut app, &def, &writer, "fee", Some("Protocol fee"), None).unwrap_err_contains("not an owner");
+        set_metadata(&mut app, &def, &def.owner, "missing", Some("Missing"), None).unwrap_err_contains("Key not found: missing");
+        set_metadata(&mut app, &def, &def.owner, "fee", None, Some(vec![""])).unwrap_err_contains("empty tag");
+
+        set_metadata(&mut app, &def, &def.owner, "fee", Some("Protocol fee"), Some(vec!["fees", "dashboard", "fees"])).unwrap();
+        set_metadata(&mut app, &def, &def.owner, "rate", None, Some(vec!["fees"])).unwrap();
+        set_metadata(&mut app, &def, &def.owner, "limit", None, Some(vec!["fees"])).unwrap();
+
+        let metadata = qy_variable_with_meta(&app, &def, "fee").unwrap().metadata.unwrap();
+        assert_eq!(metadata.description, Some("Protocol fee".to_string()));
+        assert_eq!(metadata.tags, vec!["dashboard".to_string(), "fees".to_string()]);
+
+        assert_eq!(qy_variables_by_tag(&app, &def, "dashboard", None).unwrap(), vec![("fee".to_string(), var_1.clone())]);
+        assert_eq!(qy_variables_by_tag(&app, &def, "fees", None).unwrap(), vec![("fee".to_string(), var_1.clone()), ("limit".to_string(), var_1.clone())]);
+        assert_eq!(qy_variables_by_tag(&app, &def, "fees", Some("limit")).unwrap(), vec![("rate".to_string(), var_1.clone())]);
+
+        // Replacing the tags updates the index
+        set_metadata(&mut app, &def, &def.owner, "fee", None, Some(vec!["dashboard"])).unwrap();
+        assert_eq!(qy_variables_by_tag(&app, &def, "fees", None).unwrap(), vec![("limit".to_string(), var_1.clone()), ("rate".to_string(), var_1.clone())]);
+        assert_eq!(qy_variable_with_meta(&app, &def, "fee").unwrap().metadata.unwrap().description, Some("Protocol fee".to_string()));
     }
 
-    // --- Err update unexisting variables ---
+    // --- Updated on write ---
     {
-        update_variables(&mut app, &def, &def.owner, vec![("var_20", &var_2), ("var_3", &var_3)]).unwrap_err_contains("Key not found: var_20"); 
+        grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "").unwrap();
+        app.update_block(next_block);
+        update_variable(&mut app, &def, &writer, "fee", &var_2).unwrap();
+
+        let metadata = qy_variable_with_meta(&app, &def, "fee").unwrap().metadata.unwrap();
+        assert_eq!(metadata.created, created);
+        assert_eq!(metadata.updated, Modification { height: app.block_info().height, time: app.block_info().time, sender: writer.clone() });
+        assert_eq!(metadata.tags, vec!["dashboard".to_string()]);
+
+        let all = qy_all_variables_with_meta(&app, &def).unwrap();
+        assert_eq!(all.iter().map(|(key, variable)| (key.as_str(), variable.value.clone())).collect::<Vec<_>>(), vec![("fee", var_2.clone()), ("limit", var_1.clone()), ("rate", var_1.clone())]);
     }
 
-    // --- Err update owner ---
+    // --- Removed with the variable ---
     {
-        // sanity check
-        update_owners(&mut app, &def, &random_addr, None, None).unwrap_err_contains("not an owner");
-        
-        update_owners(&mut app, &def, &def.owner, None, Some(&[&def.owner])).unwrap_err_contains("Invalid 0 owners. Needed at least 1");
-        update_owners(&mut app, &def, &def.owner, Some(&[&def.owner]), None).unwrap_err_contains("is alredy a owner");
-        let old_owner = def.owner.clone();
-        def.owner = app.generate_addr("new_owner");
-        update_owners(&mut app, &def, &old_owner, Some(&[&def.owner]), Some(&[&old_owner])).unwrap();
+        remove_variable(&mut app, &def, &writer, "fee").unwrap();
+        assert_eq!(qy_variables_by_tag(&app, &def, "dashboard", None).unwrap(), vec![]);
+
+        register_variable(&mut app, &def, &writer, "fee", &var_1).unwrap();
+        let metadata = qy_variable_with_meta(&app, &def, "fee").unwrap().metadata.unwrap();
+        assert_eq!((metadata.description, metadata.tags), (None, vec![]));
     }
+}
 
-    // --- Assert config ---
+#[test]
+#[rustfmt::skip]
+fn freeze() {
+    let (mut app, def) = startup();
+
+    let admin = app.generate_addr("admin");
+    let writer = app.generate_addr("writer");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    register_variables(&mut app, &def, &def.owner, vec![("fee", &var_1), ("rate", &var_1), ("limit", &var_1)]).unwrap();
+    grant_role(&mut app, &def, &def.owner, &admin, Role::Admin, "").unwrap();
+    grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "").unwrap();
+
+    // --- Freeze until a height ---
     {
-        let config = qy_config(&mut app, &def).unwrap();
-        assert_eq!(config.owners.len(), 1);
-        assert_eq!(config.owners[0], def.owner);
+        let height = app.block_info().height;
+        let until = Expiration::AtHeight(height + 2);
+
+        freeze_variable(&mut app, &def, &writer, "fee", None).unwrap_err_contains("has no role");
+        freeze_variable(&mut app, &def, &admin, "missing", None).unwrap_err_contains("Key not found: missing");
+        freeze_variable(&mut app, &def, &admin, "fee", Some(Expiration::AtHeight(height))).unwrap_err_contains("already reached");
+        freeze_variable(&mut app, &def, &admin, "fee", Some(until)).unwrap();
+
+        assert_eq!(qy_frozen_variables(&app, &def).unwrap(), vec![("fee".to_string(), until)]);
+
+        update_variable(&mut app, &def, &writer, "fee", &var_2).unwrap_err_contains("Key fee is frozen");
+        remove_variable(&mut app, &def, &def.owner, "fee").unwrap_err_contains("Key fee is frozen");
+
+        app.update_block(next_block);
+        app.update_block(next_block);
+
+        assert_eq!(qy_frozen_variables(&app, &def).unwrap(), vec![]);
+        update_variable(&mut app, &def, &writer, "fee", &var_2).unwrap();
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
     }
 
-    // --- Assert variables ---
+    // --- Unfreeze ---
     {
-        assert_eq!(var_1, qy_variable(&mut app, &def, "var_1").unwrap());
-        assert_eq!(
-            BTreeMap::from([("var_1".to_string(), var_1.clone()), ("var_2".to_string(), var_2.clone())]),
-            qy_variables(&mut app, &def, &["var_1", "var_2"]).unwrap()
-        );
-        assert_eq!(
-           vec![("var_1".to_string(), var_1.clone()), ("var_2".to_string(), var_2.clone())],
-            qy_all_variables(&mut app, &def, None, Some(2)).unwrap()
-        );
-        assert_eq!(
-            vec![("var_3".to_string(), var_3.clone()), ("var_4".to_string(), var_4.clone())],
-             qy_all_variables(&mut app, &def, Some("var_2"), None).unwrap()
-         );
+        let height = app.block_info().height;
+
+        unfreeze_variable(&mut app, &def, &admin, "rate").unwrap_err_contains("Key rate is not frozen");
+
+        freeze_variable(&mut app, &def, &admin, "rate", Some(Expiration::AtHeight(height + 100))).unwrap();
+        update_variable(&mut app, &def, &writer, "rate", &var_2).unwrap_err_contains("Key rate is frozen");
+
+        unfreeze_variable(&mut app, &def, &writer, "rate").unwrap_err_contains("has no role");
+        unfreeze_variable(&mut app, &def, &admin, "rate").unwrap();
+        update_variable(&mut app, &def, &writer, "rate", &var_2).unwrap();
+    }
+
+    // --- Freeze forever ---
+    {
+        let height = app.block_info().height;
+
+        schedule_variable(&mut app, &def, &writer, "limit", &var_2, Scheduled::AtHeight(height + 10)).unwrap();
+        freeze_variable(&mut app, &def, &admin, "limit", None).unwrap_err_contains("cancel the scheduled values");
+        cancel_scheduled(&mut app, &def, &writer, "limit", Scheduled::AtHeight(height + 10)).unwrap();
+
+        freeze_variable(&mut app, &def, &admin, "limit", None).unwrap();
+        assert_eq!(qy_frozen_variables(&app, &def).unwrap(), vec![("limit".to_string(), Expiration::Never {})]);
+
+        unfreeze_variable(&mut app, &def, &admin, "limit").unwrap_err_contains("Key limit is frozen");
+        freeze_variable(&mut app, &def, &admin, "limit", Some(Expiration::AtHeight(height + 1))).unwrap_err_contains("Key limit is frozen");
+        schedule_variable(&mut app, &def, &writer, "limit", &var_2, Scheduled::AtHeight(height + 10)).unwrap_err_contains("Key limit is frozen");
+        remove_variable(&mut app, &def, &def.owner, "limit").unwrap_err_contains("Key limit is frozen");
+
+        app.update_block(next_block);
+        update_variable(&mut app, &def, &def.owner, "limit", &var_2).unwrap_err_contains("Key limit is frozen");
+        assert_eq!(qy_variable(&app, &def, "limit").unwrap(), var_1);
+    }
+
+    // --- Expiring keys ---
+    {
+        let height = app.block_info().height;
+
+        register_expiring_variable(&mut app, &def, &def.owner, "token", &var_1, Expiration::AtHeight(height + 1)).unwrap();
+        freeze_variable(&mut app, &def, &admin, "token", None).unwrap_err_contains("Key token expires expiration height: ");
+        assert_eq!(qy_frozen_variables(&app, &def).unwrap(), vec![("limit".to_string(), Expiration::Never {})]);
+
+        app.update_block(next_block);
+
+        qy_variable(&app, &def, "token").unwrap_err();
+        register_variable(&mut app, &def, &def.owner, "token", &var_2).unwrap();
+        assert_eq!(qy_variable(&app, &def, "token").unwrap(), var_2);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn pause_and_unpause() {
+    let (mut app, def) = startup();
+
+    let guardian = app.generate_addr("guardian");
+    let guardian_2 = app.generate_addr("guardian_2");
+
+    let var_1 = Variable::U64(1);
+    let var_2 = Variable::U64(2);
+
+    register_variable(&mut app, &def, &def.owner, "fee", &var_1).unwrap();
+
+    // --- Set guardian ---
+    {
+        pause(&mut app, &def, &def.owner).unwrap_err_contains("Unauthorized");
+        set_guardian(&mut app, &def, &guardian, Some(&guardian)).unwrap_err_contains("not an owner");
+        set_guardian(&mut app, &def, &def.owner, Some(&def.owner)).unwrap_err_contains("The guardian can't be an owner");
+        set_guardian(&mut app, &def, &def.owner, Some(&guardian)).unwrap();
+
+        assert_eq!(qy_config(&app, &def).unwrap(), Config { owners: vec![def.owner.clone()], threshold: 1, guardian: Some(guardian.clone()), paused: false, max_reference_depth: DEFAULT_MAX_REFERENCE_DEPTH });
+    }
+
+    // --- Guardian can't become an owner ---
+    {
+        nominate_owner(&mut app, &def, &def.owner, &guardian, None).unwrap();
+        accept_ownership(&mut app, &def, &guardian).unwrap_err_contains("The guardian can't be an owner");
+
+        assert_eq!(qy_config(&app, &def).unwrap().owners, vec![def.owner.clone()]);
+    }
+
+    // --- Paused ---
+    {
+        unpause(&mut app, &def, &guardian).unwrap_err_contains("Contract is not paused");
+        pause(&mut app, &def, &def.owner).unwrap_err_contains("Unauthorized");
+        pause(&mut app, &def, &guardian).unwrap();
+        pause(&mut app, &def, &guardian).unwrap_err_contains("Contract is paused");
+
+        assert!(qy_config(&app, &def).unwrap().paused);
+
+        update_variable(&mut app, &def, &def.owner, "fee", &var_2).unwrap_err_contains("Contract is paused");
+        register_variable(&mut app, &def, &def.owner, "rate", &var_2).unwrap_err_contains("Contract is paused");
+        remove_variable(&mut app, &def, &def.owner, "fee").unwrap_err_contains("Contract is paused");
+        prune_expired(&mut app, &def, &guardian, None).unwrap_err_contains("Contract is paused");
+
+        // Queries are still allowed
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_1);
+    }
+
+    // --- Replace the guardian while paused ---
+    {
+        set_guardian(&mut app, &def, &def.owner, Some(&guardian_2)).unwrap();
+
+        unpause(&mut app, &def, &guardian).unwrap_err_contains("Unauthorized");
+        unpause(&mut app, &def, &guardian_2).unwrap();
+
+        update_variable(&mut app, &def, &def.owner, "fee", &var_2).unwrap();
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), var_2);
+
+        set_guardian(&mut app, &def, &def.owner, None).unwrap();
+        pause(&mut app, &def, &guardian_2).unwrap_err_contains("Unauthorized");
+        assert_eq!(qy_config(&app, &def).unwrap().guardian, None);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn ownership_transfer() {
+    let (mut app, def) = startup();
+
+    let owner_2 = app.generate_addr("owner_2");
+    let owner_3 = app.generate_addr("owner_3");
+    let random_addr = app.generate_addr("random_addr");
+
+    // --- Nominate ---
+    {
+        let height = app.block_info().height;
+
+        nominate_owner(&mut app, &def, &random_addr, &owner_2, None).unwrap_err_contains("not an owner");
+        nominate_owner(&mut app, &def, &def.owner, &def.owner, None).unwrap_err_contains("is alredy a owner");
+        nominate_owner(&mut app, &def, &def.owner, &owner_2, Some(Expiration::AtHeight(height))).unwrap_err_contains("Nomination of");
+        nominate_owner(&mut app, &def, &def.owner, &owner_2, None).unwrap();
+        nominate_owner(&mut app, &def, &def.owner, &owner_3, Some(Expiration::AtHeight(height + 1))).unwrap();
+
+        let mut nominations = vec![(owner_2.clone(), Expiration::Never {}), (owner_3.clone(), Expiration::AtHeight(height + 1))];
+        nominations.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(qy_owner_nominations(&app, &def).unwrap(), nominations);
+
+        // Nominees are not owners until they accept
+        assert_eq!(qy_config(&app, &def).unwrap().owners, vec![def.owner.clone()]);
+        register_variable(&mut app, &def, &owner_2, "fee", &Variable::U64(1)).unwrap_err_contains("not an owner");
+    }
+
+    // --- Accept ---
+    {
+        accept_ownership(&mut app, &def, &random_addr).unwrap_err_contains("No pending nomination");
+        accept_ownership(&mut app, &def, &owner_2).unwrap();
+        accept_ownership(&mut app, &def, &owner_2).unwrap_err_contains("No pending nomination");
+
+        assert_eq!(qy_config(&app, &def).unwrap().owners, vec![def.owner.clone(), owner_2.clone()]);
+        register_variable(&mut app, &def, &owner_2, "fee", &Variable::U64(1)).unwrap();
+    }
+
+    // --- Expire ---
+    {
+        app.update_block(next_block);
+
+        assert_eq!(qy_owner_nominations(&app, &def).unwrap(), vec![]);
+        accept_ownership(&mut app, &def, &owner_3).unwrap_err_contains("expired");
     }
 
+    // --- Revoke ---
+    {
+        revoke_nomination(&mut app, &def, &def.owner, &random_addr).unwrap_err_contains("No pending nomination");
+
+        update_owners(&mut app, &def, &owner_2, Some(&[&owner_3]), None).unwrap();
+        assert_eq!(qy_owner_nominations(&app, &def).unwrap(), vec![(owner_3.clone(), Expiration::Never {})]);
+
+        revoke_nomination(&mut app, &def, &owner_3, &owner_3).unwrap_err_contains("not an owner");
+        revoke_nomination(&mut app, &def, &owner_2, &owner_3).unwrap();
+
+        assert_eq!(qy_owner_nominations(&app, &def).unwrap(), vec![]);
+        accept_ownership(&mut app, &def, &owner_3).unwrap_err_contains("No pending nomination");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn private_keys() {
+    let (mut app, def) = startup();
+
+    let writer = app.generate_addr("writer");
+    let reader = app.generate_addr("reader");
+    let random_addr = app.generate_addr("random_addr");
+
+    let fee = Variable::U64(1);
+    let endpoint = Variable::String("https://internal.endpoint".to_string());
+
+    register_variables(&mut app, &def, &def.owner, vec![("fee", &fee), ("internal.endpoint", &endpoint)]).unwrap();
+    grant_role(&mut app, &def, &def.owner, &writer, Role::Writer, "").unwrap();
+
+    // --- Set private ---
+    {
+        set_visibility(&mut app, &def, &writer, "internal.endpoint", false).unwrap_err_contains("has no role");
+        set_visibility(&mut app, &def, &def.owner, "internal.endpoint", false).unwrap();
+
+        qy_variable(&app, &def, "internal.endpoint").unwrap_err_contains("variable is private - key: internal.endpoint");
+        qy_variables(&app, &def, &["fee", "internal.endpoint"]).unwrap_err_contains("variable is private");
+        qy_variable_history(&app, &def, "internal.endpoint", None, None).unwrap_err_contains("variable is private");
+        assert_eq!(qy_all_variables(&app, &def, None, None).unwrap(), vec![("fee".to_string(), fee.clone())]);
+        assert_eq!(qy_variable(&app, &def, "fee").unwrap(), fee);
+    }
+
+    // --- Values of private keys are redacted in the responses ---
+    {
+        let attributes = |res: &AppResponse, ty: &str, key: &str| -> Vec<String> {
+            res.events.iter().filter(|event| event.ty == ty).flat_map(|event| event.attributes.iter()).filter(|attr| attr.key == key).map(|attr| attr.value.clone()).collect()
+        };
+
+        let secret = Variable::String("topsecret".to_string());
+
+        let res = update_variable(&mut app, &def, &def.owner, "internal.endpoint", &secret).unwrap();
+        assert!(!format!("{:?}", res.events).contains("topsecret"));
+        assert_eq!(attributes(&res, "wasm", "value"), vec!["<private>"]);
+        assert_eq!(attributes(&res, "wasm-variable_updated", "old_value"), vec!["<private>"]);
+        assert_eq!(attributes(&res, "wasm-variable_updated", "new_value"), vec!["<private>"]);
+
+        let res = propose_update(&mut app, &def, &def.owner, "internal.endpoint", &endpoint).unwrap();
+        assert_eq!(attributes(&res, "wasm", "value"), vec!["<private>"]);
+        let res = execute_pending(&mut app, &def, &def.owner, "internal.endpoint").unwrap();
+        assert!(!format!("{:?}", res.events).contains("https://internal.endpoint"));
+        assert_eq!(attributes(&res, "wasm", "value"), vec!["<private>"]);
+
+        let res = update_variable(&mut app, &def, &def.owner, "fee", &fee).unwrap();
+        assert_eq!(attributes(&res, "wasm", "value"), vec!["u64: 1"]);
+    }
+
+    // --- Read through execute ---
+    {
+        assert_eq!(read_variables(&mut app, &def, &random_addr, &["fee"]).unwrap(), BTreeMap::from([("fee".to_string(), fee.clone())]));
+        read_variables(&mut app, &def, &random_addr, &["fee", "internal.endpoint"]).unwrap_err_contains("has no role allowing to read the private key internal.endpoint");
+        read_variables(&mut app, &def, &reader, &["internal.endpoint"]).unwrap_err_contains("has no role allowing to read");
+
+        grant_role(&mut app, &def, &def.owner, &reader, Role::Reader, "internal.").unwrap();
+        let expected = BTreeMap::from([("fee".to_string(), fee.clone()), ("internal.endpoint".to_string(), endpoint.clone())]);
+        assert_eq!(read_variables(&mut app, &def, &reader, &["fee", "internal.endpoint"]).unwrap(), expected);
+        assert_eq!(read_variables(&mut app, &def, &writer, &["fee", "internal.endpoint"]).unwrap(), expected);
+        assert_eq!(read_variables(&mut app, &def, &def.owner, &["fee", "internal.endpoint"]).unwrap(), expected);
+
+        read_variables(&mut app, &def, &reader, &["internal.missing"]).unwrap_err_contains("Key not found: internal.missing");
+        update_variable(&mut app, &def, &reader, "internal.endpoint", &fee).unwrap_err_contains("has no role allowing to modify");
+    }
+
+    // --- The flag is kept after remove ---
+    {
+        remove_variable(&mut app, &def, &writer, "internal.endpoint").unwrap();
+        register_variable(&mut app, &def, &writer, "internal.endpoint", &endpoint).unwrap();
+        qy_variable(&app, &def, "internal.endpoint").unwrap_err_contains("variable is private");
+
+        set_visibility(&mut app, &def, &def.owner, "internal.endpoint", true).unwrap();
+        assert_eq!(qy_variable(&app, &def, "internal.endpoint").unwrap(), endpoint);
+    }
+
+    // --- Pending updates and proposals ---
+    {
+        let owner_2 = app.generate_addr("owner_2");
+
+        set_visibility(&mut app, &def, &def.owner, "internal.endpoint", false).unwrap();
+
+        propose_update(&mut app, &def, &writer, "internal.endpoint", &endpoint).unwrap();
+        propose_update(&mut app, &def, &writer, "fee", &fee).unwrap();
+        qy_pending_update(&app, &def, "internal.endpoint").unwrap_err_contains("variable is private");
+        assert_eq!(qy_pending_update(&app, &def, "fee").unwrap().unwrap().value, fee);
+        assert_eq!(qy_all_pending_updates(&app, &def).unwrap().into_iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!["fee"]);
+
+        update_owners(&mut app, &def, &def.owner, Some(&[&owner_2]), None).unwrap();
+        accept_ownership(&mut app, &def, &owner_2).unwrap();
+        update_threshold(&mut app, &def, &def.owner, 2).unwrap();
+
+        update_variable(&mut app, &def, &def.owner, "internal.endpoint", &fee).unwrap();
+        update_variable(&mut app, &def, &def.owner, "fee", &endpoint).unwrap();
+
+        let proposals = qy_proposals(&app, &def).unwrap();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].0, 2);
+        qy_proposal(&app, &def, 1).unwrap_err_contains("variable is private - key: internal.endpoint");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn events() {
+    let (mut app, def) = startup();
+
+    let owner_2 = app.generate_addr("owner_2");
+
+    let var_1 = Variable::Uint128(100u128.into());
+    let var_2 = Variable::Uint128(200u128.into());
+
+    let events = |res: &AppResponse, ty: &str| -> Vec<BTreeMap<String, String>> {
+        res.events.iter().filter(|event| event.ty == ty).map(|event| event.attributes.iter().map(|attr| (attr.key.clone(), attr.value.clone())).collect()).collect()
+    };
+
+    let event = |namespace: &str, key: &str, kind: &str, old: &str, new: &str, sender: &Addr| -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("_contract_address".to_string(), def.vm_addr.to_string()),
+            ("namespace".to_string(), namespace.to_string()),
+            ("key".to_string(), key.to_string()),
+            ("kind".to_string(), kind.to_string()),
+            ("old_value".to_string(), old.to_string()),
+            ("new_value".to_string(), new.to_string()),
+            ("sender".to_string(), sender.to_string()),
+        ])
+    };
+
+    register_variables(&mut app, &def, &def.owner, vec![("fee", &var_1), ("rate", &var_1)]).unwrap();
+
+    // --- One event per key ---
+    {
+        let res = batch(&mut app, &def, &def.owner, vec![
+            Operation::Register(RegisterVariableMsg::new("limit".to_string(), var_1.clone())),
+            Operation::Update(RegisterVariableMsg::new("fee".to_string(), var_2.clone())),
+            Operation::Remove(RemoveVariableMsg { namespace: None, key: "rate".to_string() }),
+        ]).unwrap();
+
+        assert_eq!(events(&res, "wasm-variable_registered"), vec![event(DEFAULT_NAMESPACE, "limit", "Uint128", "null", r#"{"uint128":"100"}"#, &def.owner)]);
+        assert_eq!(events(&res, "wasm-variable_updated"), vec![event(DEFAULT_NAMESPACE, "fee", "Uint128", r#"{"uint128":"100"}"#, r#"{"uint128":"200"}"#, &def.owner)]);
+        assert_eq!(events(&res, "wasm-variable_removed"), vec![event(DEFAULT_NAMESPACE, "rate", "Uint128", r#"{"uint128":"100"}"#, "null", &def.owner)]);
+
+        let res = remove_variables(&mut app, &def, &def.owner, vec!["fee", "limit"]).unwrap();
+        assert_eq!(events(&res, "wasm-variable_removed").iter().map(|event| event["key"].as_str()).collect::<Vec<_>>(), vec!["fee", "limit"]);
+    }
+
+    // --- Owners updated ---
+    {
+        let res = update_owners(&mut app, &def, &def.owner, Some(&[&owner_2]), None).unwrap();
+        assert!(events(&res, "wasm-owners_updated").is_empty());
+
+        let res = accept_ownership(&mut app, &def, &owner_2).unwrap();
+        let updated = &events(&res, "wasm-owners_updated")[0];
+        assert_eq!(updated["old_owners"], format!(r#"["{}"]"#, def.owner));
+        assert_eq!(updated["new_owners"], format!(r#"["{}","{}"]"#, def.owner, owner_2));
+        assert_eq!(updated["threshold"], "1");
+
+        let res = update_threshold(&mut app, &def, &def.owner, 2).unwrap();
+        let updated = &events(&res, "wasm-owners_updated")[0];
+        assert_eq!(updated["old_owners"], updated["new_owners"]);
+        assert_eq!(updated["threshold"], "2");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn references() {
+    let (mut app, def) = startup();
+
+    let code_id = store_vm_code(&mut app);
+    let other = Def { owner: def.owner.clone(), vm_addr: instantiate_vm(&mut app, code_id, &def.owner, "other_vm") };
+
+    let oracle = Variable::Addr(app.generate_addr("oracle"));
+    let random_addr = app.generate_addr("random_addr");
+
+    let reference = |manager: Option<&Addr>, key: &str| Variable::Reference(VariableReference { manager: manager.cloned(), namespace: None, key: key.to_string() });
+
+    register_variable(&mut app, &other, &other.owner, "oracle", &oracle).unwrap();
+    register_variable(&mut app, &other, &other.owner, "price_oracle", &reference(None, "oracle")).unwrap();
+
+    // --- Resolve ---
+    {
+        register_variable(&mut app, &def, &def.owner, "oracle", &reference(Some(&other.vm_addr), "price_oracle")).unwrap();
+        register_variable(&mut app, &def, &def.owner, "alias", &reference(None, "oracle")).unwrap();
+
+        assert_eq!(qy_variable(&app, &def, "alias").unwrap(), oracle);
+        assert_eq!(qy_variable(&app, &def, "oracle").unwrap(), oracle);
+        assert_eq!(qy_variables(&app, &def, &["alias"]).unwrap(), BTreeMap::from([("alias".to_string(), oracle.clone())]));
+        assert_eq!(read_variables(&mut app, &def, &random_addr, &["alias"]).unwrap(), BTreeMap::from([("alias".to_string(), oracle.clone())]));
+
+        assert_eq!(qy_variable_raw(&app, &def, "alias").unwrap(), reference(None, "oracle"));
+        assert_eq!(qy_variable_raw(&app, &other, "price_oracle").unwrap(), reference(None, "oracle"));
+    }
+
+    // --- Invalid references ---
+    {
+        register_variable(&mut app, &def, &def.owner, "invalid", &Variable::Reference(VariableReference { manager: Some(Addr::unchecked("invalid")), namespace: None, key: "oracle".to_string() })).unwrap_err();
+        register_variable(&mut app, &def, &def.owner, "list", &Variable::List(vec![reference(None, "oracle")])).unwrap_err_contains("Reference not allowed inside List and Map");
+
+        register_variable(&mut app, &def, &def.owner, "missing", &reference(None, "not_registered")).unwrap();
+        qy_variable(&app, &def, "missing").unwrap_err_contains("variable not found - key: not_registered");
+    }
+
+    // --- Cycles ---
+    {
+        register_variable(&mut app, &def, &def.owner, "a", &reference(None, "b")).unwrap();
+        register_variable(&mut app, &def, &def.owner, "b", &reference(Some(&other.vm_addr), "c")).unwrap();
+        register_variable(&mut app, &other, &other.owner, "c", &reference(Some(&def.vm_addr), "a")).unwrap();
+
+        qy_variable(&app, &def, "a").unwrap_err_contains("reference cycle detected - key: a");
+        qy_variable(&app, &def, "b").unwrap_err_contains("reference cycle detected - key: b");
+    }
+
+    // --- Max depth ---
+    {
+        set_max_reference_depth(&mut app, &def, &random_addr, 1).unwrap_err_contains("not an owner");
+        set_max_reference_depth(&mut app, &def, &def.owner, 2).unwrap();
+
+        assert_eq!(qy_config(&app, &def).unwrap().max_reference_depth, 2);
+        assert_eq!(qy_variable(&app, &def, "oracle").unwrap(), oracle);
+        qy_variable(&app, &def, "alias").unwrap_err_contains("reference depth exceeded - max depth: 2");
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn factory() {
+    let (mut app, def) = startup();
+
+    let code_id = store_vm_code(&mut app);
+    let factory_addr = instantiate_factory(&mut app, &def.owner, code_id);
+
+    let random_addr = app.generate_addr("random_addr");
+    let protocol_owner = app.generate_addr("protocol_owner");
+
+    // --- Create ---
+    {
+        create_manager(&mut app, &factory_addr, &random_addr, "mars", &protocol_owner).unwrap_err_contains("Unauthorized");
+        create_manager(&mut app, &factory_addr, &def.owner, "Mars", &protocol_owner).unwrap_err_contains("Invalid protocol name");
+        create_manager(&mut app, &factory_addr, &def.owner, "", &protocol_owner).unwrap_err_contains("Invalid protocol name");
+
+        let height = app.block_info().height;
+
+        create_manager(&mut app, &factory_addr, &def.owner, "mars", &protocol_owner).unwrap();
+        create_manager(&mut app, &factory_addr, &def.owner, "mars", &protocol_owner).unwrap_err_contains("Protocol mars already registered");
+
+        let mars = qy_manager(&app, &factory_addr, "mars").unwrap();
+
+        assert_eq!(mars.protocol, "mars");
+        assert_eq!(mars.code_id, code_id);
+        assert_eq!(mars.created_at, height);
+        assert_eq!(app.wrap().query_wasm_contract_info(&mars.address).unwrap().admin, Some(protocol_owner.to_string()));
+
+        let mars = Def { owner: protocol_owner.clone(), vm_addr: mars.address };
+
+        assert_eq!(qy_config(&app, &mars).unwrap().owners, vec![protocol_owner.clone()]);
+
+        qy_manager(&app, &factory_addr, "astroport").unwrap_err();
+    }
+
+    // --- Resolve ---
+    {
+        let oracle = app.generate_addr("oracle");
+        let mars = Def { owner: protocol_owner.clone(), vm_addr: qy_manager(&app, &factory_addr, "mars").unwrap().address };
+
+        register_variable(&mut app, &mars, &protocol_owner, "oracle", &Variable::Addr(oracle.clone())).unwrap();
+
+        assert_eq!(factory_get_manager(&app.wrap(), "mars", &factory_addr).unwrap(), mars.vm_addr);
+        assert_eq!(factory_get_variable::<Addr>(&app.wrap(), "mars", None, "oracle", &factory_addr).unwrap(), oracle);
+        assert!(matches!(factory_get_variable::<Addr>(&app.wrap(), "astroport", None, "oracle", &factory_addr).unwrap_err(), VariableError::Std(_)));
+    }
+
+    // --- List ---
+    {
+        let new_code_id = store_vm_code(&mut app);
+
+        set_manager_code_id(&mut app, &factory_addr, &random_addr, new_code_id).unwrap_err_contains("Unauthorized");
+        set_manager_code_id(&mut app, &factory_addr, &def.owner, new_code_id).unwrap();
+
+        create_manager(&mut app, &factory_addr, &def.owner, "astroport", &protocol_owner).unwrap();
+        create_manager(&mut app, &factory_addr, &def.owner, "osmosis", &protocol_owner).unwrap();
+
+        assert_eq!(qy_manager(&app, &factory_addr, "astroport").unwrap().code_id, new_code_id);
+        assert_eq!(qy_manager(&app, &factory_addr, "mars").unwrap().code_id, code_id);
+
+        let protocols = |managers: Vec<ManagerInfo>| managers.into_iter().map(|manager| manager.protocol).collect::<Vec<String>>();
+
+        assert_eq!(protocols(qy_managers(&app, &factory_addr, None, None).unwrap()), vec!["astroport", "mars", "osmosis"]);
+        assert_eq!(protocols(qy_managers(&app, &factory_addr, None, Some(2)).unwrap()), vec!["astroport", "mars"]);
+        assert_eq!(protocols(qy_managers(&app, &factory_addr, Some("mars"), None).unwrap()), vec!["osmosis"]);
+    }
+
+    // --- Update owners ---
+    {
+        let new_owner = app.generate_addr("new_owner");
+
+        update_factory_owners(&mut app, &factory_addr, &random_addr, Some(vec![&new_owner]), None).unwrap_err_contains("Unauthorized");
+        update_factory_owners(&mut app, &factory_addr, &def.owner, None, None).unwrap_err_contains("Empty update owners msg");
+        update_factory_owners(&mut app, &factory_addr, &def.owner, Some(vec![&def.owner]), None).unwrap_err_contains("Address is already an owner");
+        update_factory_owners(&mut app, &factory_addr, &def.owner, None, Some(vec![&random_addr])).unwrap_err_contains("Address is not an owner");
+        update_factory_owners(&mut app, &factory_addr, &def.owner, None, Some(vec![&def.owner])).unwrap_err_contains("Invalid 0 owners");
+
+        update_factory_owners(&mut app, &factory_addr, &def.owner, Some(vec![&new_owner]), Some(vec![&def.owner])).unwrap();
+
+        assert_eq!(qy_factory_config(&app, &factory_addr).unwrap().owners, vec![new_owner.clone()]);
+
+        set_manager_code_id(&mut app, &factory_addr, &def.owner, code_id).unwrap_err_contains("Unauthorized");
+        set_manager_code_id(&mut app, &factory_addr, &new_owner, code_id).unwrap();
+    }
+
+    // --- Migrate ---
+    {
+        let factory_code_id = store_factory_code(&mut app);
+
+        let res = migrate_factory(&mut app, &factory_addr, &def.owner, factory_code_id).unwrap();
+        let from_version = res.events.iter().flat_map(|event| event.attributes.iter()).find(|attr| attr.key == "from_version").map(|attr| attr.value.as_str());
+        assert_eq!(from_version, Some(env!("CARGO_PKG_VERSION")));
+
+        // A manager can't be migrated to the factory code
+        migrate_factory(&mut app, &def.vm_addr, &def.owner, factory_code_id).unwrap_err_contains("Cannot migrate from contract crates.io:variable-manager");
+    }
 }
@@ -3,3 +3,9 @@ mod unit_tests;
 
 #[cfg(test)]
 mod integration_tests;
+
+#[cfg(test)]
+mod mock_consumer;
+
+#[cfg(test)]
+mod mock_legacy;
@@ -0,0 +1,41 @@
+use cosmwasm_std::{Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult};
+use cw_storage_plus::Item;
+use rhaki_cw_plus::traits::IntoBinaryResult;
+use variable_manager_pkg::msgs::{HookMsg, VariableChangedHook};
+
+/// Key that make the consumer fail when notified
+pub const FAILING_KEY: &str = "failing";
+
+const HOOKS: Item<Vec<VariableChangedHook>> = Item::new("hooks_key");
+
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    HOOKS.save(deps.storage, &vec![])?;
+    Ok(Response::new())
+}
+
+pub fn execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: HookMsg) -> StdResult<Response> {
+    match msg {
+        HookMsg::VariableChangedHook(hook) => {
+            if hook.key == FAILING_KEY {
+                return Err(StdError::generic_err("consumer failed"));
+            }
+
+            HOOKS.update(deps.storage, |mut hooks| -> StdResult<_> {
+                hooks.push(hook);
+                Ok(hooks)
+            })?;
+
+            Ok(Response::new())
+        }
+    }
+}
+
+/// Return every hook received
+pub fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    HOOKS.load(deps.storage).into_binary()
+}
@@ -0,0 +1,50 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult};
+use cw_storage_plus::{Item, Map};
+use variable_manager_pkg::definitions::Variable;
+
+/// `Config` of version 1.0.4
+#[cw_serde]
+pub struct Config {
+    pub owners: Vec<Addr>,
+}
+
+const CONFIG: Item<Config> = Item::new("config_key");
+
+const VARIABLES: Map<String, Variable> = Map::new("variables_key");
+
+/// Store `owners` and `variables` with the 1.0.4 layout
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owners: Vec<Addr>,
+    pub variables: Vec<(String, Variable)>,
+    /// cw2 `(contract, version)` to store, to simulate deployments of other contracts or versions
+    pub contract_version: Option<(String, String)>,
+}
+
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    CONFIG.save(deps.storage, &Config { owners: msg.owners })?;
+
+    for (key, variable) in msg.variables {
+        VARIABLES.save(deps.storage, key, &variable)?;
+    }
+
+    if let Some((contract, version)) = msg.contract_version {
+        cw2::set_contract_version(deps.storage, contract, version)?;
+    }
+
+    Ok(Response::new())
+}
+
+pub fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+pub fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Ok(Binary::default())
+}
@@ -1,5 +1,7 @@
 use std::collections::BTreeMap;
 
+use cosmwasm_std::{coin, Timestamp};
+use cw_utils::Duration;
 use rhaki_cw_plus::{
     serde_value::{json, ToCwJson, Value},
     traits::{IntoAddr, IntoBinary},
@@ -29,3 +31,24 @@ fn parse_btreemap() {
     assert_eq!(des, map)
 
 }
+
+#[test]
+#[rustfmt::skip]
+fn display_and_unwrap() {
+
+    let list = Variable::List(vec![Variable::Bool(true), Variable::Bool(false)]);
+    let map = Variable::Map(BTreeMap::from([("a".to_string(), Variable::I64(-1)), ("b".to_string(), Variable::I64(2))]));
+    let coins = Variable::Coins(vec![coin(1, "uatom"), coin(2, "uosmo")]);
+
+    assert_eq!(list.to_string(), "List: [Bool: true, Bool: false]");
+    assert_eq!(map.to_string(), "Map: {a: i64: -1, b: i64: 2}");
+    assert_eq!(coins.to_string(), "Coins: 1uatom,2uosmo");
+    assert_eq!(Variable::Duration(Duration::Time(10)).to_string(), "Duration: time: 10");
+
+    assert!(list.unwrap_list().unwrap()[0].unwrap_bool().unwrap());
+    assert_eq!(map.unwrap_map().unwrap()["a"].unwrap_i64().unwrap(), -1);
+    assert_eq!(coins.unwrap_coins().unwrap(), vec![coin(1, "uatom"), coin(2, "uosmo")]);
+    assert_eq!(Variable::Timestamp(Timestamp::from_seconds(1)).unwrap_timestamp().unwrap(), Timestamp::from_seconds(1));
+    assert!(coins.unwrap_coin().is_err());
+
+}